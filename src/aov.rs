@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::f64::INFINITY;
+use std::sync::Arc;
+
+use crate::hittables::hittable::{Hittable, HittableTrait, object_id};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Color, Vec3};
+
+/// Auxiliary per-pixel buffers (arbitrary output variables) describing the surfaces seen by the
+/// camera, e.g. for compositing or denoising.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Aov {
+    /// The reflectance of the first surface hit.
+    Albedo,
+    /// The shading normal of the first surface hit, facing the camera.
+    Normal,
+    /// The distance along the camera ray.
+    Depth,
+    Position,
+    /// The texture coordinates in the red and green channels.
+    Uv,
+    /// The number of the primitive in the scene, starting at 1.
+    ObjectId,
+    /// The number of the material in the scene, starting at 1.
+    MaterialId,
+}
+
+impl Aov {
+    /// The name used in file names.
+    pub fn name(self) -> &'static str {
+        return match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::Uv => "uv",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+        };
+    }
+
+    /// Ids cannot be averaged, they are taken from a single ray through the center of a pixel.
+    pub fn is_id(self) -> bool {
+        return self == Aov::ObjectId || self == Aov::MaterialId;
+    }
+}
+
+/// Numbers the primitives and materials of a scene in the order they were added, so ids stay the
+/// same between renders of the same scene. Rays that leave the scene have the id 0.
+pub struct AovIds {
+    objects: HashMap<usize, usize>,
+    materials: HashMap<usize, usize>,
+}
+
+impl AovIds {
+    pub fn new(hittables: &Vec<Arc<Hittable>>) -> AovIds {
+        let mut ids = AovIds {
+            objects: HashMap::new(),
+            materials: HashMap::new(),
+        };
+        for hittable in hittables.iter() {
+            ids.collect(hittable);
+        }
+        return ids;
+    }
+
+    fn collect(&mut self, hittable: &Hittable) {
+        match hittable {
+            Hittable::Sphere { sphere } => self.add(object_id(sphere), &sphere.material),
+            Hittable::Triangle { triangle } => self.add(object_id(triangle), &triangle.texture),
+            Hittable::Bvh { bvh } => {
+                self.collect(&bvh.left);
+                self.collect(&bvh.right);
+            }
+            Hittable::HittableList { hittable_list } => {
+                for hittable in hittable_list.list.iter() {
+                    self.collect(hittable);
+                }
+            }
+            Hittable::Moving { moving } => self.collect(&moving.hittable),
+        }
+    }
+
+    fn add(&mut self, object: usize, material: &Arc<Material>) {
+        let next = self.objects.len() + 1;
+        self.objects.entry(object).or_insert(next);
+        let next = self.materials.len() + 1;
+        self.materials.entry(Arc::as_ptr(material) as *const () as usize).or_insert(next);
+    }
+
+    /// Finds the first surface along the ray and returns the values of all AOVs for it. Rays that
+    /// leave the scene give zero everywhere.
+    pub fn sample(&self, world: &Hittable, ray: &Ray) -> AovSample {
+        let rec = match world.hit(ray, 0.0001, INFINITY) {
+            Some(rec) => rec,
+            None => return AovSample::default(),
+        };
+        let material = Arc::as_ptr(&rec.material) as *const () as usize;
+        return AovSample {
+            albedo: rec.material.albedo(&rec),
+            normal: rec.normal,
+            depth: rec.t * ray.direction.length(),
+            position: rec.point,
+            uv: (rec.u, rec.v),
+            object_id: self.objects.get(&rec.object).copied().unwrap_or(0),
+            material_id: self.materials.get(&material).copied().unwrap_or(0),
+        };
+    }
+}
+
+#[derive(Default)]
+pub struct AovSample {
+    pub albedo: Color,
+    pub normal: Vec3,
+    pub depth: f64,
+    pub position: Vec3,
+    pub uv: (f64, f64),
+    pub object_id: usize,
+    pub material_id: usize,
+}
+
+impl AovSample {
+    pub fn value(&self, aov: Aov) -> Color {
+        return match aov {
+            Aov::Albedo => self.albedo,
+            Aov::Normal => self.normal,
+            Aov::Depth => gray(self.depth),
+            Aov::Position => self.position,
+            Aov::Uv => Color { e: [self.uv.0, self.uv.1, 0.0] },
+            Aov::ObjectId => gray(self.object_id as f64),
+            Aov::MaterialId => gray(self.material_id as f64),
+        };
+    }
+}
+
+fn gray(value: f64) -> Color {
+    return Color { e: [value, value, value] };
+}
@@ -1,13 +1,50 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::hittables::hittable::{Hittable, HittableTrait};
 use crate::ray::Ray;
+use crate::samplers::sampler::Sampler;
+use crate::samplers::warp::sample_disk;
 use crate::utils::math_utils::deg_to_rad;
-use crate::vec3::{cross, Point3, Vec3};
+use crate::vec3::{cross, dot, Point3, Vec3};
+
+/// How directions are mapped to the image.
+#[derive(Clone, Copy)]
+pub enum Projection {
+    Perspective,
+    /// Parallel rays along the viewing direction through a view of the given height in world
+    /// units, e.g. for technical drawings.
+    Orthographic { height: f64 },
+    /// A 360° by 180° panorama, best rendered with an aspect ratio of 2:1.
+    Equirectangular,
+    /// An equidistant fisheye whose field of view in degrees spans the diagonal of the image.
+    Fisheye { fov: f64 },
+}
 
+/// A thin-lens camera. Rays start on a disk with radius `lens_radius` around `origin` and meet on
+/// the plane `focus_dist` in front of the camera, so only objects on that plane are sharp. A
+/// radius of zero gives a pinhole camera where everything is in focus. The lens and `vfov` only
+/// apply to the perspective projection.
+///
+/// Every ray is given a time between `shutter_open` and `shutter_close`, which blurs moving
+/// objects. The camera itself stands still.
 #[derive(Clone, Copy)]
 pub struct Camera {
     origin: Vec3,
     lower_left_corner: Vec3,
     horizontal: Vec3,
     vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    look_at: Point3,
+    vup: Vec3,
+    vfov: f64,
+    focus_dist: f64,
+    lens_radius: f64,
+    shutter_open: f64,
+    shutter_close: f64,
+    projection: Projection,
 }
 
 pub fn create_camera(
@@ -17,6 +54,7 @@ pub fn create_camera(
     vfov: f64,
     aspect_ratio: f64,
     focus_dist: f64,
+    lens_radius: f64,
 ) -> Camera {
     let theta = deg_to_rad(vfov);
     let h = (theta / 2.0).tan();
@@ -37,14 +75,123 @@ pub fn create_camera(
         lower_left_corner,
         horizontal,
         vertical,
+        u,
+        v,
+        w,
+        look_at,
+        vup,
+        vfov,
+        focus_dist,
+        lens_radius,
+        shutter_open: 0.0,
+        shutter_close: 0.0,
+        projection: Projection::Perspective,
     };
 }
 
 impl Camera {
-    pub(crate) fn get_ray(self, s: f64, t: f64) -> Ray {
-        return Ray::new(
+    /// Rebuilds the camera for a different image shape, keeping its position and orientation.
+    pub fn with_aspect_ratio(&self, aspect_ratio: f64) -> Camera {
+        return self.with_view(aspect_ratio, self.focus_dist);
+    }
+
+    /// Rebuilds the camera with the plane of focus at the given distance.
+    pub fn with_focus_dist(&self, focus_dist: f64) -> Camera {
+        return self.with_view(self.aspect_ratio(), focus_dist);
+    }
+
+    fn with_view(&self, aspect_ratio: f64, focus_dist: f64) -> Camera {
+        let camera = create_camera(
             self.origin,
-            self.lower_left_corner + (self.horizontal * s) + (self.vertical * t) - self.origin,
+            self.look_at,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            focus_dist,
+            self.lens_radius,
         );
+        return Camera {
+            lower_left_corner: camera.lower_left_corner,
+            horizontal: camera.horizontal,
+            vertical: camera.vertical,
+            focus_dist,
+            ..*self
+        };
+    }
+
+    pub fn with_projection(&self, projection: Projection) -> Camera {
+        let mut camera = *self;
+        camera.projection = projection;
+        return camera;
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        return self.horizontal.length() / self.vertical.length();
+    }
+
+    /// Sets the interval in which the shutter is open. The unit of time is the one used by the
+    /// keyframes of moving objects.
+    pub fn with_shutter(&self, open: f64, close: f64) -> Camera {
+        let mut camera = *self;
+        camera.shutter_open = open;
+        camera.shutter_close = close;
+        return camera;
+    }
+
+    /// Focuses on whatever is visible at the image coordinates `s` and `t`, which run from 0 to 1
+    /// left to right and bottom to top. Returns `None` if nothing is there.
+    pub fn autofocus(&self, hittables: &Vec<Arc<Hittable>>, s: f64, t: f64) -> Option<Camera> {
+        // Look through the center of the lens at the moment the shutter opens.
+        let ray = self.ray_through(s, t, Vec3::new(), self.shutter_open);
+        let closest = hittables
+            .iter()
+            .filter_map(|hittable| hittable.hit(&ray, 0.0001, f64::INFINITY))
+            .min_by(|a, b| a.t.total_cmp(&b.t))?;
+        // The focus distance is measured along the viewing direction, not along the ray.
+        let distance = dot(&(closest.point - self.origin), &-self.w);
+        return Some(self.with_focus_dist(distance));
+    }
+
+    /// The ray through the image coordinates `s` and `t`, with the point on the lens and the time
+    /// taken from the sampler.
+    pub fn get_ray(self, s: f64, t: f64, sampler: &mut Sampler) -> Ray {
+        let rd = sample_disk(sampler.get_2d()) * self.lens_radius;
+        let time = self.shutter_open + (self.shutter_close - self.shutter_open).max(0.0) * sampler.get_1d();
+        return self.ray_through(s, t, self.u * rd.x() + self.v * rd.y(), time);
+    }
+
+    /// The ray through the image coordinates `s` and `t` at `time`, starting at `lens_offset`
+    /// from the center of the lens.
+    fn ray_through(&self, s: f64, t: f64, lens_offset: Vec3, time: f64) -> Ray {
+        return match self.projection {
+            Projection::Perspective => Ray::new(
+                self.origin + lens_offset,
+                self.lower_left_corner + (self.horizontal * s) + (self.vertical * t) - self.origin - lens_offset,
+                time,
+            ),
+            Projection::Orthographic { height } => {
+                let width = height * self.aspect_ratio();
+                let origin = self.origin + self.u * ((s - 0.5) * width) + self.v * ((t - 0.5) * height);
+                Ray::new(origin, -self.w, time)
+            }
+            Projection::Equirectangular => {
+                let phi = (s - 0.5) * 2.0 * PI;
+                let theta = (t - 0.5) * PI;
+                let direction = -self.w * (theta.cos() * phi.cos()) + self.u * (theta.cos() * phi.sin()) + self.v * theta.sin();
+                Ray::new(self.origin, direction, time)
+            }
+            Projection::Fisheye { fov } => {
+                // Position on the image relative to its center, in units of the half diagonal.
+                let aspect_ratio = self.aspect_ratio();
+                let x = (s - 0.5) * aspect_ratio;
+                let y = t - 0.5;
+                let half_diagonal = 0.5 * (aspect_ratio * aspect_ratio + 1.0).sqrt();
+                let r = (x * x + y * y).sqrt() / half_diagonal;
+                let theta = r * deg_to_rad(fov) / 2.0;
+                let phi = y.atan2(x);
+                let direction = -self.w * theta.cos() + (self.u * phi.cos() + self.v * phi.sin()) * theta.sin();
+                Ray::new(self.origin, direction, time)
+            }
+        };
     }
 }
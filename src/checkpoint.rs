@@ -0,0 +1,185 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::Path;
+
+use crate::filter::Filter;
+use crate::renderer::Accumulation;
+use crate::samplers::sampler::SamplingMethod;
+use crate::settings::RenderSettings;
+use crate::vec3::Color;
+
+const MAGIC: &[u8; 4] = b"RTCP";
+const VERSION: u32 = 5;
+
+/// The accumulated samples of a render and the settings that determine which samples were taken
+/// and how they were weighted, which a resumed render has to match.
+pub struct Checkpoint {
+    pub accumulation: Accumulation,
+    pub seed: u64,
+    pub sampler: SamplingMethod,
+    /// The stratified sampler divides the pixel into as many strata as there are samples, so its
+    /// samples only add up for the same count.
+    pub samples_per_pixel: usize,
+    pub filter: Filter,
+}
+
+impl Checkpoint {
+    /// Checks that the settings continue the render the samples were taken for, and describes the
+    /// first difference otherwise.
+    pub fn check(&self, settings: &RenderSettings) -> Result<(), String> {
+        let accumulation = &self.accumulation;
+        if accumulation.width != settings.width || accumulation.height != settings.height {
+            return Err(format!(
+                "is {}x{}, but the image is {}x{}",
+                accumulation.width, accumulation.height, settings.width, settings.height
+            ));
+        }
+        // Other samples or weights would not add up with the accumulated ones.
+        if self.seed != settings.seed {
+            return Err(format!("has seed {}, but the render uses seed {}", self.seed, settings.seed));
+        }
+        if self.sampler != settings.sampler {
+            return Err(format!(
+                "uses the {:?} sampler, but the render uses the {:?} sampler",
+                self.sampler, settings.sampler
+            ));
+        }
+        if self.sampler == SamplingMethod::Stratified && self.samples_per_pixel != settings.samples_per_pixel {
+            return Err(format!(
+                "is stratified for {} samples per pixel, but the render takes {}",
+                self.samples_per_pixel, settings.samples_per_pixel
+            ));
+        }
+        if self.filter != settings.filter {
+            return Err(format!(
+                "uses the filter {:?}, but the render uses the filter {:?}",
+                self.filter, settings.filter
+            ));
+        }
+        return Ok(());
+    }
+}
+
+/// Saves the accumulated samples so a later render can continue from them. The checkpoint is
+/// written to a temporary file first, so an interrupted write never destroys the last one.
+pub fn write_checkpoint(accumulation: &Accumulation, settings: &RenderSettings, path: &Path) -> std::io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    {
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&accumulation.width.to_le_bytes())?;
+        file.write_all(&accumulation.height.to_le_bytes())?;
+        file.write_all(&settings.seed.to_le_bytes())?;
+        file.write_all(&sampler_code(settings.sampler).to_le_bytes())?;
+        file.write_all(&(settings.samples_per_pixel as u64).to_le_bytes())?;
+        file.write_all(&filter_code(&settings.filter).to_le_bytes())?;
+        file.write_all(&settings.filter.radius().to_le_bytes())?;
+        for i in 0..accumulation.sums.len() {
+            for c in accumulation.sums[i].e.iter() {
+                file.write_all(&c.to_le_bytes())?;
+            }
+            file.write_all(&accumulation.weights[i].to_le_bytes())?;
+            file.write_all(&accumulation.luminances[i].to_le_bytes())?;
+            file.write_all(&accumulation.squares[i].to_le_bytes())?;
+            file.write_all(&accumulation.samples[i].to_le_bytes())?;
+        }
+        file.flush()?;
+    }
+    return fs::rename(&temporary, path);
+}
+
+pub fn read_checkpoint(path: &Path) -> std::io::Result<Checkpoint> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "not a checkpoint file"));
+    }
+    if read_u32(&mut file)? != VERSION {
+        return Err(Error::new(ErrorKind::InvalidData, "unsupported checkpoint version"));
+    }
+    let width = read_u32(&mut file)? as i32;
+    let height = read_u32(&mut file)? as i32;
+    if width <= 0 || height <= 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "invalid image size"));
+    }
+    let seed = read_u64(&mut file)?;
+    let sampler = sampler_from_code(read_u32(&mut file)?)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "unknown sampler"))?;
+    let samples_per_pixel = read_u64(&mut file)? as usize;
+    let filter_code = read_u32(&mut file)?;
+    let filter = filter_from_code(filter_code, read_f64(&mut file)?)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "unknown filter"))?;
+    let mut accumulation = Accumulation::new(width, height);
+    for i in 0..accumulation.sums.len() {
+        let mut sum = Color::new();
+        for c in sum.e.iter_mut() {
+            *c = read_f64(&mut file)?;
+        }
+        accumulation.sums[i] = sum;
+        accumulation.weights[i] = read_f64(&mut file)?;
+        accumulation.luminances[i] = read_f64(&mut file)?;
+        accumulation.squares[i] = read_f64(&mut file)?;
+        accumulation.samples[i] = read_u32(&mut file)?;
+    }
+    return Ok(Checkpoint { accumulation, seed, sampler, samples_per_pixel, filter });
+}
+
+fn sampler_code(sampler: SamplingMethod) -> u32 {
+    return match sampler {
+        SamplingMethod::Random => 0,
+        SamplingMethod::Stratified => 1,
+        SamplingMethod::Halton => 2,
+        SamplingMethod::Sobol => 3,
+    };
+}
+
+fn sampler_from_code(code: u32) -> Option<SamplingMethod> {
+    return match code {
+        0 => Some(SamplingMethod::Random),
+        1 => Some(SamplingMethod::Stratified),
+        2 => Some(SamplingMethod::Halton),
+        3 => Some(SamplingMethod::Sobol),
+        _ => None,
+    };
+}
+
+fn filter_code(filter: &Filter) -> u32 {
+    return match filter {
+        Filter::Box { .. } => 0,
+        Filter::Tent { .. } => 1,
+        Filter::Gaussian { .. } => 2,
+        Filter::Mitchell { .. } => 3,
+        Filter::Lanczos { .. } => 4,
+    };
+}
+
+fn filter_from_code(code: u32, radius: f64) -> Option<Filter> {
+    return match code {
+        0 => Some(Filter::Box { radius }),
+        1 => Some(Filter::Tent { radius }),
+        2 => Some(Filter::Gaussian { radius }),
+        3 => Some(Filter::Mitchell { radius }),
+        4 => Some(Filter::Lanczos { radius }),
+        _ => None,
+    };
+}
+
+fn read_u32(file: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    file.read_exact(&mut bytes)?;
+    return Ok(u32::from_le_bytes(bytes));
+}
+
+fn read_u64(file: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+    return Ok(u64::from_le_bytes(bytes));
+}
+
+fn read_f64(file: &mut impl Read) -> std::io::Result<f64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+    return Ok(f64::from_le_bytes(bytes));
+}
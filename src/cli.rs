@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use raytracer::aov::Aov;
+use raytracer::color::ToneMapOperator;
+use raytracer::filter::{Filter, FilterKind};
+use raytracer::image::BitDepth;
+use raytracer::samplers::sampler::SamplingMethod;
+use raytracer::settings::RenderSettings;
+
+#[derive(Parser)]
+#[command(name = "raytracer", about = "Renders TOML scene descriptions")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Renders a scene to an image file
+    Render(RenderArgs),
+    /// Prints the settings and the contents of a scene without rendering it
+    Info {
+        /// The scene description file
+        scene: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BitDepthArg {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+}
+
+impl BitDepthArg {
+    pub fn bit_depth(self) -> BitDepth {
+        return match self {
+            BitDepthArg::Eight => BitDepth::Eight,
+            BitDepthArg::Sixteen => BitDepth::Sixteen,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ToneMapArg {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl ToneMapArg {
+    pub fn operator(self) -> ToneMapOperator {
+        return match self {
+            ToneMapArg::Clamp => ToneMapOperator::Clamp,
+            ToneMapArg::Reinhard => ToneMapOperator::Reinhard,
+            ToneMapArg::Aces => ToneMapOperator::Aces,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FilterArg {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterArg {
+    /// The filter with its default radius.
+    pub fn filter(self) -> Filter {
+        return Filter::with_default_radius(match self {
+            FilterArg::Box => FilterKind::Box,
+            FilterArg::Tent => FilterKind::Tent,
+            FilterArg::Gaussian => FilterKind::Gaussian,
+            FilterArg::Mitchell => FilterKind::Mitchell,
+            FilterArg::Lanczos => FilterKind::Lanczos,
+        });
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SamplerArg {
+    Random,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerArg {
+    pub fn method(self) -> SamplingMethod {
+        return match self {
+            SamplerArg::Random => SamplingMethod::Random,
+            SamplerArg::Stratified => SamplingMethod::Stratified,
+            SamplerArg::Halton => SamplingMethod::Halton,
+            SamplerArg::Sobol => SamplingMethod::Sobol,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum AovArg {
+    Albedo,
+    Normal,
+    Depth,
+    Position,
+    Uv,
+    ObjectId,
+    MaterialId,
+}
+
+impl AovArg {
+    pub fn aov(self) -> Aov {
+        return match self {
+            AovArg::Albedo => Aov::Albedo,
+            AovArg::Normal => Aov::Normal,
+            AovArg::Depth => Aov::Depth,
+            AovArg::Position => Aov::Position,
+            AovArg::Uv => Aov::Uv,
+            AovArg::ObjectId => Aov::ObjectId,
+            AovArg::MaterialId => Aov::MaterialId,
+        };
+    }
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// The scene description file
+    pub scene: PathBuf,
+
+    /// The file the rendered image is written to, as .ppm, .png, .pfm, .hdr or .exr
+    #[arg(short, long, default_value = "resources/renders/output.ppm")]
+    pub output: PathBuf,
+
+    /// An additional file for the linear, unclamped image, as .pfm, .hdr or .exr
+    #[arg(long)]
+    pub hdr_output: Option<PathBuf>,
+
+    /// The number of bits per channel of PNG images
+    #[arg(long, value_enum, default_value = "8")]
+    pub bit_depth: BitDepthArg,
+
+    /// The image width in pixels, overriding the scene
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub width: Option<i32>,
+
+    /// The image height in pixels, overriding the scene
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub height: Option<i32>,
+
+    /// The number of samples per pixel, overriding the scene
+    #[arg(short, long, value_parser = positive_count)]
+    pub samples: Option<usize>,
+
+    /// The number of samples added to every pixel per pass over the image, overriding the scene
+    #[arg(long)]
+    pub samples_per_pass: Option<usize>,
+
+    /// How the samples of a pixel are distributed, overriding the scene
+    #[arg(long, value_enum)]
+    pub sampler: Option<SamplerArg>,
+
+    /// The reconstruction filter that weights the samples into the pixels, overriding the scene
+    #[arg(long, value_enum)]
+    pub filter: Option<FilterArg>,
+
+    /// The radius of the reconstruction filter in pixels, overriding the default of the filter
+    #[arg(long, value_parser = positive)]
+    pub filter_radius: Option<f64>,
+
+    /// The seed of the random decisions, overriding the scene. The same seed gives the same image
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Stop sampling pixels whose relative noise is below this threshold, e.g. 0.01, overriding the scene
+    #[arg(long, value_parser = positive)]
+    pub noise_threshold: Option<f64>,
+
+    /// The number of samples every pixel gets before it may stop early, overriding the scene
+    #[arg(long)]
+    pub min_samples: Option<usize>,
+
+    /// Write an image of the number of samples per pixel, from blue for none to red for the maximum
+    #[arg(long)]
+    pub sample_heatmap: Option<PathBuf>,
+
+    /// Save the accumulated samples to this file while rendering, so the render can be resumed
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// The minimum number of seconds between two checkpoints
+    #[arg(long, default_value_t = 60)]
+    pub checkpoint_interval: u64,
+
+    /// Continue from the checkpoint file if it exists, e.g. after a crash or to add more samples
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// The maximum number of bounces per path, overriding the scene
+    #[arg(short, long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub depth: Option<i32>,
+
+    /// The number of bounces before Russian roulette may terminate a path, overriding the scene
+    #[arg(long, value_parser = clap::value_parser!(i32).range(0..))]
+    pub min_bounces: Option<i32>,
+
+    /// Disable Russian roulette, e.g. for reference renders
+    #[arg(long)]
+    pub no_russian_roulette: bool,
+
+    /// Only find lights by following the materials instead of sampling them directly. Point, spot
+    /// and directional lights are still sampled, as no path can hit them
+    #[arg(long)]
+    pub no_light_sampling: bool,
+
+    /// Auxiliary images to write next to the output as <name>.<aov>.exr, e.g. `--aov albedo,normal`
+    #[arg(long = "aov", value_enum, value_delimiter = ',')]
+    pub aovs: Vec<AovArg>,
+
+    /// Filter the noise out of the image using its albedo, normal and depth
+    #[arg(long)]
+    pub denoise: bool,
+
+    /// The tone mapping operator for display images, overriding the scene
+    #[arg(long, value_enum)]
+    pub tone_mapping: Option<ToneMapArg>,
+
+    /// The exposure adjustment in stops, overriding the scene
+    #[arg(long, allow_negative_numbers = true)]
+    pub exposure: Option<f64>,
+
+    /// The number of worker threads, one per core by default
+    #[arg(short, long)]
+    pub threads: Option<usize>,
+
+    /// The side length in pixels of the tiles the threads render, overriding the scene
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub tile_size: Option<i32>,
+
+    /// Do not open the preview window
+    #[arg(long)]
+    pub no_preview: bool,
+}
+
+impl RenderArgs {
+    /// Applies the command-line overrides to the settings of the scene. When only one side of the
+    /// image size is given, the other one is derived from the aspect ratio of the scene.
+    pub fn apply(&self, settings: &mut RenderSettings) {
+        let aspect_ratio = settings.aspect_ratio();
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => {
+                settings.width = width;
+                settings.height = height;
+            }
+            (Some(width), None) => {
+                settings.width = width;
+                settings.height = ((width as f64 / aspect_ratio) as i32).max(1);
+            }
+            (None, Some(height)) => {
+                settings.width = ((height as f64 * aspect_ratio) as i32).max(1);
+                settings.height = height;
+            }
+            (None, None) => {}
+        }
+        if let Some(samples) = self.samples {
+            settings.samples_per_pixel = samples;
+        }
+        if let Some(samples_per_pass) = self.samples_per_pass {
+            settings.samples_per_pass = samples_per_pass.max(1);
+        }
+        if let Some(sampler) = self.sampler {
+            settings.sampler = sampler.method();
+        }
+        if let Some(filter) = self.filter {
+            settings.filter = filter.filter();
+        }
+        if let Some(radius) = self.filter_radius {
+            settings.filter = settings.filter.with_radius(radius);
+        }
+        if let Some(seed) = self.seed {
+            settings.seed = seed;
+        }
+        if let Some(noise_threshold) = self.noise_threshold {
+            settings.noise_threshold = Some(noise_threshold);
+        }
+        if let Some(min_samples) = self.min_samples {
+            settings.min_samples_per_pixel = min_samples;
+        }
+        if let Some(depth) = self.depth {
+            settings.depth = depth;
+        }
+        if let Some(min_bounces) = self.min_bounces {
+            settings.min_bounces = min_bounces;
+        }
+        if self.no_russian_roulette {
+            settings.russian_roulette = false;
+        }
+        if self.no_light_sampling {
+            settings.light_sampling = false;
+        }
+        if self.denoise {
+            settings.denoise = true;
+        }
+        if let Some(tone_mapping) = self.tone_mapping {
+            settings.tone_mapping.operator = tone_mapping.operator();
+        }
+        if let Some(exposure) = self.exposure {
+            settings.tone_mapping.exposure = exposure;
+        }
+        if let Some(threads) = self.threads {
+            settings.threads = threads.max(1);
+        }
+        if let Some(tile_size) = self.tile_size {
+            settings.tile_size = tile_size;
+        }
+    }
+}
+
+fn positive(value: &str) -> Result<f64, String> {
+    let number: f64 = value.parse().map_err(|_| format!("`{}` is not a number", value))?;
+    if !number.is_finite() || number <= 0.0 {
+        return Err(String::from("must be positive"));
+    }
+    return Ok(number);
+}
+
+fn positive_count(value: &str) -> Result<usize, String> {
+    let number: usize = value.parse().map_err(|_| format!("`{}` is not a whole number", value))?;
+    if number == 0 {
+        return Err(String::from("must be positive"));
+    }
+    return Ok(number);
+}
@@ -1,25 +1,70 @@
+use std::io::Write;
+
+use serde::Deserialize;
+
 use crate::utils::math_utils::clamp;
 use crate::vec3::Color;
-use std::fs::File;
-use std::io::Write;
 
-pub fn write_color(file: &mut File, pixel_color: Color, samples_per_pixel: usize) {
-    let new_col = scale_color(&pixel_color, samples_per_pixel);
-    // Write the translated [0,255] value of each color component.
-    write!(file, "{} ", (256.0 * clamp(new_col.x(), 0.0, 0.999)) as i32).expect("Cannot write to file.");
-    write!(file, "{} ", (256.0 * clamp(new_col.y(), 0.0, 0.999)) as i32).expect("Cannot write to file.");
-    write!(file, "{} \n", (256.0 * clamp(new_col.z(), 0.0, 0.999)) as i32).expect("Cannot write to file.");
+/// The curves that compress the unbounded radiance of a render into the displayable range.
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapOperator {
+    /// Cuts off everything brighter than white.
+    Clamp,
+    /// `c / (1 + c)` per channel, which never reaches white.
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+/// How linear radiance is turned into display colors: the radiance is scaled by `2^exposure`,
+/// tone mapped and encoded with the sRGB transfer function.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    /// The exposure adjustment in stops.
+    pub exposure: f64,
 }
 
-pub fn scale_color(color: &Color, samples_per_pixel: usize) -> Color{
-    let mut r = color.x();
-    let mut g = color.y();
-    let mut b = color.z();
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0.0,
+        }
+    }
+}
+
+pub fn write_color(file: &mut impl Write, pixel_color: Color, samples_per_pixel: usize, tone_mapping: &ToneMapping) -> std::io::Result<()> {
+    let new_col = scale_color(&pixel_color, samples_per_pixel, tone_mapping);
+    // Write the translated [0,255] value of each color component.
+    write!(file, "{} ", (256.0 * clamp(new_col.x(), 0.0, 0.999)) as i32)?;
+    write!(file, "{} ", (256.0 * clamp(new_col.y(), 0.0, 0.999)) as i32)?;
+    write!(file, "{} \n", (256.0 * clamp(new_col.z(), 0.0, 0.999)) as i32)
+}
 
+/// Converts an accumulated color to a display color with components in [0, 1].
+pub fn scale_color(color: &Color, samples_per_pixel: usize, tone_mapping: &ToneMapping) -> Color{
     // Divide the color by the number of samples.
-    let scale = 1.0 / (samples_per_pixel as f64);
-    r = (scale * r).sqrt();
-    g = (scale * g).sqrt();
-    b = (scale * b).sqrt();
-    return Color{ e: [r, g, b] };
-}
\ No newline at end of file
+    let scale = 2f64.powf(tone_mapping.exposure) / (samples_per_pixel as f64);
+    let mut result = [0.0; 3];
+    for (i, c) in color.e.iter().enumerate() {
+        let c = (scale * c).max(0.0);
+        let mapped = match tone_mapping.operator {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => c / (1.0 + c),
+            ToneMapOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        };
+        result[i] = linear_to_srgb(clamp(mapped, 0.0, 1.0));
+    }
+    return Color{ e: result };
+}
+
+/// The sRGB transfer function.
+fn linear_to_srgb(c: f64) -> f64 {
+    return if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+}
@@ -0,0 +1,118 @@
+use crate::image::Image;
+use crate::vec3::{Color, Vec3};
+
+/// An edge-avoiding À-trous wavelet filter ("Edge-Avoiding À-Trous Wavelet Transform for fast
+/// Global Illumination Filtering", Dammertz et al. 2010). The image is blurred with a 5x5 kernel
+/// whose taps are spread further apart in every iteration, and every tap is weighted by how
+/// similar its color, normal and depth are to the center, so edges stay sharp.
+///
+/// The color is divided by the albedo before filtering, so textures are not blurred either.
+#[derive(Clone, Copy)]
+pub struct Denoiser {
+    pub iterations: u32,
+    /// How much the tone mapped colors may differ, halved in every iteration.
+    pub sigma_color: f64,
+    /// How much the normals may differ.
+    pub sigma_normal: f64,
+    /// How much the depths may differ, relative to the depth of the center.
+    pub sigma_depth: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser {
+            iterations: 5,
+            sigma_color: 0.5,
+            sigma_normal: 0.3,
+            sigma_depth: 0.05,
+        }
+    }
+}
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+impl Denoiser {
+    /// Filters `color` using the albedo, normal and depth AOVs of the same render.
+    pub fn denoise(&self, color: &Image, albedo: &Image, normal: &Image, depth: &Image) -> Image {
+        let mut illumination = color.clone();
+        for (pixel, a) in illumination.pixels.iter_mut().zip(albedo.pixels.iter()) {
+            *pixel = demodulate(pixel, a);
+        }
+
+        let mut step = 1;
+        let mut sigma_color = self.sigma_color;
+        for _ in 0..self.iterations {
+            illumination = self.filter(&illumination, normal, depth, step, sigma_color);
+            step *= 2;
+            sigma_color /= 2.0;
+        }
+
+        for (pixel, a) in illumination.pixels.iter_mut().zip(albedo.pixels.iter()) {
+            *pixel = modulate(pixel, a);
+        }
+        return illumination;
+    }
+
+    fn filter(&self, image: &Image, normal: &Image, depth: &Image, step: i32, sigma_color: f64) -> Image {
+        let mut result = Image::new(image.width, image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let color_p = compress(&image.get(x, y));
+                let normal_p = normal.get(x, y);
+                let depth_p = depth.get(x, y).x();
+
+                let mut sum = Vec3::new();
+                let mut weights = 0.0;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let qx = x + (i as i32 - 2) * step;
+                        let qy = y + (j as i32 - 2) * step;
+                        if qx < 0 || qy < 0 || qx >= image.width || qy >= image.height {
+                            continue;
+                        }
+                        let color_q = image.get(qx, qy);
+                        let color_distance = (compress(&color_q) - color_p).length_squared();
+                        let normal_distance = (normal.get(qx, qy) - normal_p).length_squared();
+                        let depth_distance = (depth.get(qx, qy).x() - depth_p).abs() / depth_p.max(1e-4);
+
+                        let weight = kx * ky
+                            * (-color_distance / (sigma_color * sigma_color)).exp()
+                            * (-normal_distance / (self.sigma_normal * self.sigma_normal)).exp()
+                            * (-depth_distance / self.sigma_depth).exp();
+                        sum = sum + color_q * weight;
+                        weights += weight;
+                    }
+                }
+                // The center always has a weight of at least 9/64.
+                result.set(x, y, sum / weights);
+            }
+        }
+        return result;
+    }
+}
+
+/// Keeps colors brighter than white from dominating the color distance.
+fn compress(color: &Color) -> Color {
+    return Color { e: [color.x() / (1.0 + color.x()), color.y() / (1.0 + color.y()), color.z() / (1.0 + color.z())] };
+}
+
+fn demodulate(color: &Color, albedo: &Color) -> Color {
+    let mut result = *color;
+    for i in 0..3 {
+        if albedo.e[i] > 1e-3 {
+            result.e[i] /= albedo.e[i];
+        }
+    }
+    return result;
+}
+
+fn modulate(color: &Color, albedo: &Color) -> Color {
+    let mut result = *color;
+    for i in 0..3 {
+        if albedo.e[i] > 1e-3 {
+            result.e[i] *= albedo.e[i];
+        }
+    }
+    return result;
+}
+
@@ -0,0 +1,141 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+/// The reconstruction filter that weights the samples around the center of a pixel. Every sample
+/// contributes to all pixels within `radius`, measured in pixels along each axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// The average of the samples within the pixel for a radius of 0.5.
+    Box { radius: f64 },
+    Tent { radius: f64 },
+    /// A Gaussian with a standard deviation of a third of the radius, shifted to reach zero there.
+    Gaussian { radius: f64 },
+    /// The Mitchell-Netravali filter with B = C = 1/3, stretched to the radius.
+    Mitchell { radius: f64 },
+    /// A sinc windowed by a sinc that stretches over the radius.
+    Lanczos { radius: f64 },
+}
+
+/// The shapes of the filters, for choosing one by name.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::with_default_radius(FilterKind::Box)
+    }
+}
+
+impl Filter {
+    /// The filter of the given kind with a radius that suits it, which for the box filter covers
+    /// exactly one pixel.
+    pub fn with_default_radius(kind: FilterKind) -> Filter {
+        return match kind {
+            FilterKind::Box => Filter::Box { radius: 0.5 },
+            FilterKind::Tent => Filter::Tent { radius: 1.0 },
+            FilterKind::Gaussian => Filter::Gaussian { radius: 1.5 },
+            FilterKind::Mitchell => Filter::Mitchell { radius: 2.0 },
+            FilterKind::Lanczos => Filter::Lanczos { radius: 3.0 },
+        };
+    }
+
+    pub fn kind(&self) -> FilterKind {
+        return match self {
+            Filter::Box { .. } => FilterKind::Box,
+            Filter::Tent { .. } => FilterKind::Tent,
+            Filter::Gaussian { .. } => FilterKind::Gaussian,
+            Filter::Mitchell { .. } => FilterKind::Mitchell,
+            Filter::Lanczos { .. } => FilterKind::Lanczos,
+        };
+    }
+
+    pub fn radius(&self) -> f64 {
+        return match self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius }
+            | Filter::Mitchell { radius }
+            | Filter::Lanczos { radius } => *radius,
+        };
+    }
+
+    /// The filter of the same kind with another radius.
+    pub fn with_radius(&self, radius: f64) -> Filter {
+        return match self {
+            Filter::Box { .. } => Filter::Box { radius },
+            Filter::Tent { .. } => Filter::Tent { radius },
+            Filter::Gaussian { .. } => Filter::Gaussian { radius },
+            Filter::Mitchell { .. } => Filter::Mitchell { radius },
+            Filter::Lanczos { .. } => Filter::Lanczos { radius },
+        };
+    }
+
+    /// The weight of a sample at the offset `x`, `y` from the center of a pixel. The box, tent and
+    /// Gaussian filters integrate to one.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        return self.evaluate_1d(x) * self.evaluate_1d(y);
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let radius = self.radius();
+        return match self {
+            // Half open, so a sample on the border between two pixels only counts for one of them.
+            Filter::Box { .. } => {
+                if x > -radius && x <= radius {
+                    1.0 / (2.0 * radius)
+                } else {
+                    0.0
+                }
+            }
+            Filter::Tent { .. } => (radius - x.abs()).max(0.0) / (radius * radius),
+            Filter::Gaussian { .. } => {
+                let sigma = radius / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.0) / (GAUSSIAN_AREA * radius)
+            }
+            Filter::Mitchell { .. } => mitchell(2.0 * x / radius),
+            Filter::Lanczos { .. } => {
+                if x.abs() >= radius {
+                    0.0
+                } else {
+                    sinc(x) * sinc(x / radius)
+                }
+            }
+        };
+    }
+}
+
+/// The area under the shifted Gaussian filter of radius one:
+/// sqrt(2 PI) / 3 * erf(3 / sqrt(2)) - 2 exp(-4.5).
+const GAUSSIAN_AREA: f64 = 0.811_068_970_084_547;
+
+/// The Mitchell-Netravali cubic with B = C = 1/3, which is zero beyond 2.
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1.0 / 3.0;
+    const C: f64 = 1.0 / 3.0;
+    let x = x.abs();
+    if x >= 2.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return ((-B - 6.0 * C) * x * x * x + (6.0 * B + 30.0 * C) * x * x + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0;
+    }
+    return ((12.0 - 9.0 * B - 6.0 * C) * x * x * x + (-18.0 + 12.0 * B + 6.0 * C) * x * x + (6.0 - 2.0 * B)) / 6.0;
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    return (PI * x).sin() / (PI * x);
+}
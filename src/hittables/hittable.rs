@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::hittables::hittable_list::HittableList;
+use crate::hittables::moving::Moving;
 use crate::hittables::sphere::Sphere;
 use crate::hittables::triangle::Triangle;
 use crate::material::MaterialTrait;
@@ -9,17 +10,25 @@ use crate::ray::Ray;
 use crate::vec3::{dot, Point3, Vec3};
 
 pub struct HitRecord {
-    pub(crate) point: Point3,
-    pub(crate) normal: Vec3,
-    pub(crate) material: Arc<dyn MaterialTrait>,
-    pub(crate) t: f64,
-    pub(crate) u: f64,
-    pub(crate) v: f64,
-    pub(crate) front_face: bool,
+    pub point: Point3,
+    pub normal: Vec3,
+    pub material: Arc<dyn MaterialTrait>,
+    pub t: f64,
+    pub u: f64,
+    pub v: f64,
+    pub front_face: bool,
+    /// Identifies the primitive that was hit, see [`object_id`].
+    pub object: usize,
+}
+
+/// Primitives are shared through `Arc`s and never move while a scene exists, so their address
+/// identifies them, e.g. to find the light that belongs to an emissive primitive.
+pub fn object_id<T>(object: &T) -> usize {
+    return object as *const T as usize;
 }
 
 impl HitRecord {
-    pub(crate) fn set_face_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
+    pub fn set_face_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
         self.front_face = dot(&r.direction, outward_normal) < 0.0;
         self.normal = if self.front_face {
             *outward_normal
@@ -49,6 +58,7 @@ pub enum Hittable {
     Bvh { bvh: Bvh },
     Triangle { triangle: Triangle },
     HittableList { hittable_list: HittableList },
+    Moving { moving: Moving },
 }
 
 impl HittableTrait for Hittable {
@@ -58,6 +68,7 @@ impl HittableTrait for Hittable {
             Hittable::Bvh { bvh } => bvh.hit(ray, t_min, t_max),
             Hittable::Triangle { triangle } => triangle.hit(ray, t_min, t_max),
             Hittable::HittableList { hittable_list } => hittable_list.hit(ray, t_min, t_max),
+            Hittable::Moving { moving } => moving.hit(ray, t_min, t_max),
         };
     }
 
@@ -67,6 +78,7 @@ impl HittableTrait for Hittable {
             Hittable::Bvh { bvh } => bvh.get_min_pos(),
             Hittable::Triangle { triangle } => triangle.get_min_pos(),
             Hittable::HittableList { hittable_list } => hittable_list.get_min_pos(),
+            Hittable::Moving { moving } => moving.get_min_pos(),
         };
     }
 
@@ -76,6 +88,7 @@ impl HittableTrait for Hittable {
             Hittable::Bvh { bvh } => bvh.get_max_pos(),
             Hittable::Triangle { triangle } => triangle.get_max_pos(),
             Hittable::HittableList { hittable_list } => hittable_list.get_max_pos(),
+            Hittable::Moving { moving } => moving.get_max_pos(),
         };
     }
 
@@ -85,6 +98,7 @@ impl HittableTrait for Hittable {
             Hittable::Bvh { bvh } => bvh.get_mean_pos(),
             Hittable::Triangle { triangle } => triangle.get_mean_pos(),
             Hittable::HittableList { hittable_list } => hittable_list.get_mean_pos(),
+            Hittable::Moving { moving } => moving.get_mean_pos(),
         };
     }
 
@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct HittableList {
-    pub(crate) list: Vec<Arc<Hittable>>,
+    pub list: Vec<Arc<Hittable>>,
 }
 
 impl HittableList {
@@ -1,4 +1,5 @@
 pub mod hittable;
 pub mod hittable_list;
+pub mod moving;
 pub mod sphere;
 pub mod triangle;
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use crate::hittables::hittable::{HitRecord, Hittable, HittableTrait};
+use crate::optimizations::bvh::BBox;
+use crate::ray::Ray;
+use crate::utils::math_utils::deg_to_rad;
+use crate::vec3::{Point3, Vec3};
+
+/// The pose of a moving object at a point in time. The object is scaled and rotated around the
+/// center of its bounds, then translated.
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    /// The rotations around the x, y and z axes in degrees, applied in that order.
+    pub rotation: Vec3,
+    /// The scale along every axis, all of them positive.
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    fn lerp(&self, other: &Keyframe, f: f64) -> Keyframe {
+        return Keyframe {
+            time: self.time * (1.0 - f) + other.time * f,
+            translation: self.translation * (1.0 - f) + other.translation * f,
+            rotation: self.rotation * (1.0 - f) + other.rotation * f,
+            scale: self.scale * (1.0 - f) + other.scale * f,
+        };
+    }
+
+    fn rotate(&self, v: Vec3) -> Vec3 {
+        let v = rotate_axis(v, 0, deg_to_rad(self.rotation.x()));
+        let v = rotate_axis(v, 1, deg_to_rad(self.rotation.y()));
+        return rotate_axis(v, 2, deg_to_rad(self.rotation.z()));
+    }
+
+    fn unrotate(&self, v: Vec3) -> Vec3 {
+        let v = rotate_axis(v, 2, -deg_to_rad(self.rotation.z()));
+        let v = rotate_axis(v, 1, -deg_to_rad(self.rotation.y()));
+        return rotate_axis(v, 0, -deg_to_rad(self.rotation.x()));
+    }
+
+    fn unscale(&self, v: Vec3) -> Vec3 {
+        return Vec3 { e: [v.x() / self.scale.x(), v.y() / self.scale.y(), v.z() / self.scale.z()] };
+    }
+}
+
+/// Rotates the vector counterclockwise around one of the coordinate axes.
+fn rotate_axis(v: Vec3, axis: usize, angle: f64) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut rotated = v;
+    rotated.e[a] = cos * v.e[a] - sin * v.e[b];
+    rotated.e[b] = sin * v.e[a] + cos * v.e[b];
+    return rotated;
+}
+
+/// Moves a hittable along keyframed poses. Between two keyframes the translation, the rotation
+/// angles and the scale are interpolated linearly, before the first and after the last keyframe
+/// the object stands still.
+pub struct Moving {
+    pub hittable: Arc<Hittable>,
+    /// Sorted by time, with at least one entry.
+    pub keyframes: Vec<Keyframe>,
+    /// The point the object is scaled and rotated around.
+    pivot: Point3,
+    bounds: BBox,
+}
+
+impl Moving {
+    pub fn new(hittable: Arc<Hittable>, mut keyframes: Vec<Keyframe>) -> Moving {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let bbox = hittable.get_bbox();
+        let pivot = (bbox.bounds[0] + bbox.bounds[1]) / 2.0;
+        let bounds = motion_bounds(&bbox, pivot, &keyframes);
+        return Moving { hittable, keyframes, pivot, bounds };
+    }
+
+    pub fn keyframe_at(&self, time: f64) -> Keyframe {
+        let first = self.keyframes[0];
+        if time <= first.time {
+            return first;
+        }
+        for window in self.keyframes.windows(2) {
+            let (a, b) = (window[0], window[1]);
+            if time <= b.time {
+                return a.lerp(&b, (time - a.time) / (b.time - a.time));
+            }
+        }
+        return self.keyframes[self.keyframes.len() - 1];
+    }
+}
+
+/// The bounds of the object in every pose it takes. Scale and translation are interpolated
+/// linearly, so while the rotation does not change, the corners move on straight lines and the
+/// bounds at the keyframes cover everything in between. A changing rotation is covered by the
+/// sphere the object can turn in.
+fn motion_bounds(bbox: &BBox, pivot: Point3, keyframes: &[Keyframe]) -> BBox {
+    let [min, max] = bbox.bounds;
+    let rotating = keyframes.iter().any(|keyframe| keyframe.rotation != keyframes[0].rotation);
+    let mut points = Vec::new();
+    for keyframe in keyframes.iter() {
+        let center = pivot + keyframe.translation;
+        if rotating {
+            let largest = keyframe.scale.x().max(keyframe.scale.y()).max(keyframe.scale.z());
+            let radius = (max - min).length() / 2.0 * largest;
+            points.push(center + radius);
+            points.push(center + -radius);
+        } else {
+            for corner in 0..8 {
+                let p = Vec3 {
+                    e: [
+                        if corner & 1 == 0 { min.x() } else { max.x() },
+                        if corner & 2 == 0 { min.y() } else { max.y() },
+                        if corner & 4 == 0 { min.z() } else { max.z() },
+                    ],
+                };
+                points.push(center + keyframe.rotate((p - pivot) * keyframe.scale));
+            }
+        }
+    }
+    return points.iter().fold(BBox { bounds: [points[0], points[0]] }, |acc, p| BBox {
+        bounds: [
+            Vec3 { e: [acc.bounds[0].x().min(p.x()), acc.bounds[0].y().min(p.y()), acc.bounds[0].z().min(p.z())] },
+            Vec3 { e: [acc.bounds[1].x().max(p.x()), acc.bounds[1].y().max(p.y()), acc.bounds[1].z().max(p.z())] },
+        ],
+    });
+}
+
+impl HittableTrait for Moving {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Move the ray into the pose the object has at rest instead of moving the object. The
+        // direction is not normalised again, so the distances along both rays are the same.
+        let keyframe = self.keyframe_at(ray.time);
+        let origin = self.pivot + keyframe.unscale(keyframe.unrotate(ray.origin - keyframe.translation - self.pivot));
+        let direction = keyframe.unscale(keyframe.unrotate(ray.direction));
+        let moved = Ray::new(origin, direction, ray.time);
+        let mut rec = self.hittable.hit(&moved, t_min, t_max)?;
+        rec.point = self.pivot + keyframe.translation + keyframe.rotate((rec.point - self.pivot) * keyframe.scale);
+        // Normals transform with the inverse transpose, which undoes the scale but keeps the rotation.
+        rec.normal = keyframe.rotate(keyframe.unscale(rec.normal)).unit_vector();
+        return Some(rec);
+    }
+
+    fn get_min_pos(&self) -> Vec3 {
+        return self.bounds.bounds[0];
+    }
+
+    fn get_max_pos(&self) -> Vec3 {
+        return self.bounds.bounds[1];
+    }
+
+    fn get_mean_pos(&self) -> Vec3 {
+        return (self.get_min_pos() + self.get_max_pos()) / 2.0;
+    }
+
+    fn get_bbox(&self) -> BBox {
+        BBox {
+            bounds: [self.get_min_pos(), self.get_max_pos()],
+        }
+    }
+}
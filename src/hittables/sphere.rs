@@ -1,4 +1,4 @@
-use crate::hittables::hittable::{HitRecord, HittableTrait};
+use crate::hittables::hittable::{HitRecord, HittableTrait, object_id};
 use crate::material::Material;
 use crate::optimizations::bvh::BBox;
 use crate::ray::Ray;
@@ -8,9 +8,9 @@ use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Sphere {
-    pub(crate) position: Point3,
-    pub(crate) radius: f64,
-    pub(crate) material: Arc<Material>,
+    pub position: Point3,
+    pub radius: f64,
+    pub material: Arc<Material>,
 }
 impl Sphere {
     fn get_uv(&self, p: Point3) -> (f64, f64) {
@@ -51,6 +51,7 @@ impl HittableTrait for Sphere {
             u,
             v,
             front_face: false,
+            object: object_id(self),
         };
         rec.set_face_normal(ray, &normal);
 
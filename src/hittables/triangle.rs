@@ -1,7 +1,7 @@
 use std::f64::EPSILON;
 use std::sync::Arc;
 
-use crate::hittables::hittable::{HitRecord, HittableTrait};
+use crate::hittables::hittable::{HitRecord, HittableTrait, object_id};
 use crate::material::Material;
 use crate::optimizations::bvh::BBox;
 use crate::ray::Ray;
@@ -9,12 +9,12 @@ use crate::vec3::{cross, dot, Point3, Vec3};
 
 #[derive(Clone)]
 pub struct Triangle {
-    pub(crate) a: Point3,
-    pub(crate) b: Point3,
-    pub(crate) c: Point3,
-    pub(crate) n: Vec3,
-    pub(crate) texture: Arc<Material>,
-    pub(crate) texture_coordinates: Option<[(f64, f64); 3]>,
+    pub a: Point3,
+    pub b: Point3,
+    pub c: Point3,
+    pub n: Vec3,
+    pub texture: Arc<Material>,
+    pub texture_coordinates: Option<[(f64, f64); 3]>,
 }
 
 impl Triangle {
@@ -42,6 +42,10 @@ impl Triangle {
         };
     }
 
+    pub fn area(&self) -> f64 {
+        return cross(&(self.b - self.a), &(self.c - self.a)).length() / 2.0;
+    }
+
     pub fn new_texture_coordinates(
         a: Point3,
         b: Point3,
@@ -101,6 +105,7 @@ impl HittableTrait for Triangle {
             u: i,
             v: j,
             front_face: false,
+            object: object_id(self),
         };
         record.set_face_normal(ray, &self.n);
         return Option::from(record);
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+
+use crate::color::{scale_color, ToneMapping, write_color};
+use crate::utils::math_utils::clamp;
+use crate::vec3::{Color, Vec3};
+use crate::writers::exr::write_exr;
+use crate::writers::hdr::write_hdr;
+use crate::writers::pfm::write_pfm;
+
+/// The file formats images can be written in, chosen by the extension of the file.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Pfm,
+    Hdr,
+    Exr,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        return match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "pfm" => Some(ImageFormat::Pfm),
+            "hdr" => Some(ImageFormat::Hdr),
+            "exr" => Some(ImageFormat::Exr),
+            _ => None,
+        };
+    }
+
+    /// Whether the format stores the linear values as they are instead of a display image.
+    pub fn is_high_dynamic_range(self) -> bool {
+        return match self {
+            ImageFormat::Ppm | ImageFormat::Png => false,
+            ImageFormat::Pfm | ImageFormat::Hdr | ImageFormat::Exr => true,
+        };
+    }
+}
+
+/// The number of bits per channel of formats that support several.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+/// A rendered image holding the linear radiance of every pixel, stored row by row from the top.
+#[derive(Clone)]
+pub struct Image {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: i32, height: i32) -> Image {
+        return Image {
+            width,
+            height,
+            pixels: vec![Vec3::new(); (width * height) as usize],
+        };
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Color {
+        return self.pixels[(y * self.width + x) as usize];
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, color: Color) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    /// Writes the image in the format given by the extension of the path. The bit depth only
+    /// applies to PNG files, the tone mapping only to PPM and PNG files.
+    pub fn save(&self, path: &Path, bit_depth: BitDepth, tone_mapping: &ToneMapping) -> std::io::Result<()> {
+        return match ImageFormat::from_path(path) {
+            Some(ImageFormat::Ppm) => self.write_ppm(path, tone_mapping),
+            Some(ImageFormat::Png) => self.write_png(path, bit_depth, tone_mapping),
+            Some(ImageFormat::Pfm) => write_pfm(self, path),
+            Some(ImageFormat::Hdr) => write_hdr(self, path),
+            Some(ImageFormat::Exr) => write_exr(self, path),
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "unsupported image format, expected .ppm, .png, .pfm, .hdr or .exr",
+            )),
+        };
+    }
+
+    /// Writes the image as an ASCII (P3) PPM file.
+    pub fn write_ppm(&self, path: &Path, tone_mapping: &ToneMapping) -> std::io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "P3\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in self.pixels.iter() {
+            write_color(&mut file, *pixel, 1, tone_mapping)?;
+        }
+        return file.flush();
+    }
+
+    /// Writes the image as an RGB PNG file with 8 or 16 bits per channel.
+    pub fn write_png(&self, path: &Path, bit_depth: BitDepth, tone_mapping: &ToneMapping) -> std::io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut data = Vec::new();
+        match bit_depth {
+            BitDepth::Eight => {
+                encoder.set_depth(png::BitDepth::Eight);
+                for pixel in self.pixels.iter() {
+                    let color = scale_color(pixel, 1, tone_mapping);
+                    for c in color.e.iter() {
+                        data.push((256.0 * clamp(*c, 0.0, 0.999)) as u8);
+                    }
+                }
+            }
+            BitDepth::Sixteen => {
+                encoder.set_depth(png::BitDepth::Sixteen);
+                for pixel in self.pixels.iter() {
+                    let color = scale_color(pixel, 1, tone_mapping);
+                    for c in color.e.iter() {
+                        // PNG stores 16 bit samples in big endian order.
+                        let value = (65536.0 * clamp(*c, 0.0, 0.99999)) as u16;
+                        data.extend_from_slice(&value.to_be_bytes());
+                    }
+                }
+            }
+        }
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&data).map_err(png_error)?;
+        return writer.finish().map_err(png_error);
+    }
+}
+
+fn png_error(e: png::EncodingError) -> Error {
+    return match e {
+        png::EncodingError::IoError(e) => e,
+        e => Error::new(ErrorKind::Other, e),
+    };
+}
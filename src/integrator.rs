@@ -0,0 +1,139 @@
+use std::f64::INFINITY;
+use std::sync::Arc;
+
+use crate::hittables::hittable::{Hittable, HittableTrait};
+use crate::lights::environment::Background;
+use crate::lights::light_list::LightList;
+use crate::ray::Ray;
+use crate::samplers::sampler::Sampler;
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::utils::onb::Onb;
+use crate::vec3::{Color, Vec3};
+
+/// A path tracer that combines sampling the materials with sampling the lights of the scene
+/// directly (next-event estimation) through multiple importance sampling.
+pub struct Integrator {
+    pub world: Arc<Hittable>,
+    pub lights: LightList,
+    pub background: Background,
+    pub settings: RenderSettings,
+}
+
+impl Integrator {
+    /// Traces paths through `world`, the bounding volume hierarchy over the objects of the scene.
+    pub fn new(scene: &Scene, world: &Arc<Hittable>, settings: &RenderSettings) -> Integrator {
+        return Integrator {
+            world: world.clone(),
+            lights: LightList::new(&scene.hittables, &scene.lights, &scene.background),
+            background: scene.background.clone(),
+            settings: *settings,
+        };
+    }
+
+    /// Traces a path through the scene and returns the radiance arriving along the ray. The path
+    /// is followed iteratively for at most `settings.depth` bounces, carrying the product of the
+    /// attenuations of all scattering events so far as its throughput. The random decisions along
+    /// the path use the values of the sampler.
+    pub fn color_at(&self, r: &Ray, sampler: &mut Sampler) -> Color {
+        let light_sampling = self.settings.light_sampling && !self.lights.is_empty();
+        let mut ray = *r;
+        let mut throughput = Color { e: [1.0, 1.0, 1.0] };
+        let mut radiance = Vec3::new();
+        // The density of the last scattered direction, or `None` if it cannot be found by light
+        // sampling (camera rays and specular bounces).
+        let mut scatter_pdf: Option<f64> = None;
+
+        for bounce in 0..self.settings.depth {
+            let hit = self.world.hit(&ray, 0.0001, INFINITY);
+            let t_max = hit.as_ref().map_or(INFINITY, |rec| rec.t);
+            if let Some(light_hit) = self.lights.hit(&ray, 0.0001, t_max) {
+                let weight = match scatter_pdf {
+                    Some(pdf) if light_sampling => power_heuristic(pdf, light_hit.pdf),
+                    _ => 1.0,
+                };
+                radiance = radiance + throughput * light_hit.radiance * weight;
+                break;
+            }
+
+            let rec = match hit {
+                Some(rec) => rec,
+                None => {
+                    let weight = match (scatter_pdf, &self.background) {
+                        (Some(pdf), Background::Map { .. }) if light_sampling => {
+                            power_heuristic(pdf, self.lights.environment_pdf(&ray.direction))
+                        }
+                        _ => 1.0,
+                    };
+                    radiance = radiance + throughput * self.background.color(&ray.direction) * weight;
+                    break;
+                }
+            };
+
+            let emitted = rec.material.emitted(&rec);
+            match scatter_pdf {
+                Some(pdf) if light_sampling && emitted.max_component() > 0.0 => {
+                    let light_pdf = self.lights.pdf(&ray.origin, &rec);
+                    radiance = radiance + throughput * emitted * power_heuristic(pdf, light_pdf);
+                }
+                _ => radiance = radiance + throughput * emitted,
+            }
+
+            // The material works in a frame around the normal that faces the incoming ray.
+            let frame = Onb::from_w(&rec.normal);
+            let w_o = frame.to_local(&-ray.direction.unit_vector());
+            // Sample the lights before the material, which may fail to find a direction even
+            // though the surface is lit. Point, spot and directional lights cannot be hit by any
+            // path, so they are sampled even without light sampling.
+            if !rec.material.is_specular() {
+                let sample = self.lights.sample(&rec.point, sampler).filter(|sample| light_sampling || sample.delta);
+                if let Some(sample) = sample {
+                    // Area lights end the paths that hit them, so they block the light of the
+                    // others as well.
+                    let shadow_ray = Ray::new(rec.point, sample.direction, ray.time);
+                    let distance = sample.distance * (1.0 - 1e-4);
+                    let occluded = self.world.hit(&shadow_ray, 0.0001, distance).is_some()
+                        || self.lights.hit(&shadow_ray, 0.0001, distance).is_some();
+                    if !occluded && sample.pdf > 0.0 {
+                        let w_i = frame.to_local(&sample.direction.unit_vector());
+                        let f = rec.material.eval(&w_o, &w_i, &rec);
+                        let pdf = rec.material.pdf(&w_o, &w_i, &rec);
+                        let weight = if sample.delta { 1.0 } else { power_heuristic(sample.pdf, pdf) };
+                        radiance = radiance + throughput * f * sample.radiance * (weight / sample.pdf);
+                    }
+                }
+            }
+
+            let scatter = match rec.material.sample(&w_o, &rec, sampler) {
+                Some(scatter) => scatter,
+                None => break,
+            };
+
+            throughput = throughput * scatter.attenuation;
+            scatter_pdf = if scatter.specular { None } else { Some(scatter.pdf) };
+            ray = Ray::new(rec.point, frame.to_world(&scatter.w_i), ray.time);
+
+            // Russian roulette: continue with a probability that follows the throughput and weight
+            // the surviving paths by its inverse, which keeps the estimate unbiased.
+            if self.settings.russian_roulette && bounce + 1 >= self.settings.min_bounces {
+                let survival = throughput.max_component().min(1.0);
+                if survival <= 0.0 || sampler.get_1d() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        return radiance;
+    }
+}
+
+/// The weight of a sample taken with density `pdf` when the same direction could also have been
+/// sampled with `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    return a / (a + b);
+}
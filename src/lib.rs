@@ -0,0 +1,39 @@
+//! A CPU path tracer. Scenes are built from [`Hittable`](hittables::hittable::Hittable)s either
+//! directly or by loading a TOML description with [`load_scene`](parsers::scene::load_scene), and
+//! rendered into an [`Image`] with [`render`].
+
+pub mod aov;
+pub mod camera;
+pub mod checkpoint;
+pub mod color;
+pub mod denoiser;
+pub mod filter;
+pub mod hittables;
+pub mod image;
+pub mod integrator;
+pub mod lights;
+pub mod material;
+pub mod microfacet;
+pub mod noises;
+#[cfg(feature = "preview")]
+pub mod opengl;
+pub mod optimizations;
+pub mod parsers;
+pub mod ray;
+pub mod renderer;
+pub mod samplers;
+pub mod scene;
+pub mod settings;
+pub mod textures;
+pub mod tiles;
+pub mod utils;
+pub mod vec3;
+pub mod writers;
+
+pub use crate::camera::{Camera, create_camera, Projection};
+pub use crate::image::Image;
+pub use crate::material::Material;
+pub use crate::renderer::render;
+pub use crate::scene::Scene;
+pub use crate::settings::RenderSettings;
+pub use crate::textures::texture::Texture;
@@ -0,0 +1,114 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::image::Image;
+use crate::lights::sky::Sky;
+use crate::utils::distribution::Distribution2D;
+use crate::utils::math_utils::{clamp, deg_to_rad};
+use crate::vec3::{Color, Vec3};
+
+/// What rays that leave the scene see.
+#[derive(Clone)]
+pub enum Background {
+    Solid { color: Color },
+    /// Blends from `bottom` straight down to `top` straight up.
+    Gradient { bottom: Color, top: Color },
+    Map { map: Arc<EnvironmentMap> },
+    /// A daylight sky. Its sun is added to the lights of the scene.
+    Sky { sky: Arc<Sky> },
+}
+
+impl Background {
+    pub fn color(&self, direction: &Vec3) -> Color {
+        return match self {
+            Background::Solid { color } => *color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.unit_vector().y() + 1.0);
+                *bottom * (1.0 - t) + *top * t
+            }
+            Background::Map { map } => map.radiance(direction),
+            Background::Sky { sky } => sky.radiance(direction),
+        };
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color { e: [1.0, 1.0, 1.0] },
+            top: Color { e: [0.5, 0.7, 1.0] },
+        }
+    }
+}
+
+/// An equirectangular image surrounding the scene. Directions are sampled proportionally to the
+/// luminance of the pixels so bright regions such as the sun are found quickly.
+pub struct EnvironmentMap {
+    image: Image,
+    /// The rotation around the up axis in radians.
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    pub fn new(image: Image, rotation: f64, intensity: f64) -> EnvironmentMap {
+        let mut rows = Vec::with_capacity(image.height as usize);
+        for y in 0..image.height {
+            // Rows near the poles cover a smaller solid angle.
+            let sin_theta = (PI * (y as f64 + 0.5) / image.height as f64).sin();
+            let row = (0..image.width)
+                .map(|x| luminance(&image.get(x, y)) * sin_theta)
+                .collect();
+            rows.push(row);
+        }
+        return EnvironmentMap {
+            image,
+            rotation: deg_to_rad(rotation),
+            intensity,
+            distribution: Distribution2D::new(rows),
+        };
+    }
+
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let x = ((u * self.image.width as f64) as i32).min(self.image.width - 1);
+        let y = ((v * self.image.height as f64) as i32).min(self.image.height - 1);
+        return self.image.get(x, y) * self.intensity;
+    }
+
+    /// Samples a direction towards the environment, returning it with the radiance arriving
+    /// from it and its solid angle density.
+    pub fn sample(&self, u: (f64, f64)) -> Option<(Vec3, Color, f64)> {
+        let ((u, v), map_pdf) = self.distribution.sample(u.0, u.1);
+        let theta = v * PI;
+        let sin_theta = theta.sin();
+        if map_pdf == 0.0 || sin_theta == 0.0 {
+            return None;
+        }
+        let direction = Vec3::spherical_coordinate(theta, u * 2.0 * PI - self.rotation);
+        let pdf = map_pdf / (2.0 * PI * PI * sin_theta);
+        return Some((direction, self.radiance(&direction), pdf));
+    }
+
+    pub fn pdf(&self, direction: &Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+        return self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta);
+    }
+
+    fn direction_to_uv(&self, direction: &Vec3) -> (f64, f64) {
+        let d = direction.unit_vector();
+        let theta = clamp(d.y(), -1.0, 1.0).acos();
+        let phi = d.z().atan2(d.x()) + self.rotation;
+        let u = (phi / (2.0 * PI)).rem_euclid(1.0);
+        return (u, theta / PI);
+    }
+}
+
+pub fn luminance(color: &Color) -> f64 {
+    return 0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z();
+}
@@ -0,0 +1,299 @@
+use std::f64::consts::PI;
+use std::f64::INFINITY;
+
+use crate::hittables::sphere::Sphere;
+use crate::hittables::triangle::Triangle;
+use crate::ray::Ray;
+use crate::utils::math_utils::{clamp, deg_to_rad};
+use crate::utils::onb::Onb;
+use crate::vec3::{Color, cross, dot, Point3, Vec3};
+
+/// A direction towards a light chosen by [`LightTrait::sample`].
+pub struct LightSample {
+    /// The unit direction from the shaded point towards the light.
+    pub direction: Vec3,
+    /// The distance to the sampled point on the light.
+    pub distance: f64,
+    /// The radiance arriving from the light along `direction`.
+    pub radiance: Color,
+    /// The probability density of the direction with respect to solid angle.
+    pub pdf: f64,
+    /// Whether the light is a point or a direction that other rays cannot hit, so the sample
+    /// must not be weighted against material sampling.
+    pub delta: bool,
+}
+
+pub trait LightTrait: Send + Sync {
+    /// Samples a direction from `point` towards the light, driven by the values `u` in [0, 1).
+    fn sample(&self, point: &Point3, u: (f64, f64)) -> Option<LightSample>;
+    /// The solid angle density with which `sample` picks the direction from `origin` towards
+    /// `point` on the light.
+    fn pdf(&self, origin: &Point3, point: &Point3) -> f64;
+    /// Intersects the ray with lights that are not part of the scene geometry and returns the
+    /// distance to the hit and the radiance emitted back along the ray.
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Color)>;
+}
+
+#[derive(Clone)]
+pub enum Light {
+    /// An emissive sphere of the scene.
+    Sphere { sphere: Sphere },
+    /// An emissive triangle of the scene.
+    Triangle { triangle: Triangle },
+    /// Emits `intensity` evenly into all directions from a single point.
+    Point { position: Point3, intensity: Color },
+    /// A point light restricted to a cone around `direction`. The intensity fades out smoothly
+    /// between the angles `cone_angle - falloff` and `cone_angle`, measured from the axis.
+    Spot {
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        cos_cone: f64,
+        cos_falloff_start: f64,
+    },
+    /// Light arriving from infinitely far away along `direction`, such as sunlight, with the
+    /// given irradiance.
+    Directional { direction: Vec3, irradiance: Color },
+    /// A parallelogram spanned by `edge_u` and `edge_v` from `corner` that emits to the side its
+    /// normal `edge_u x edge_v` points to.
+    Rect {
+        corner: Point3,
+        edge_u: Vec3,
+        edge_v: Vec3,
+        emission: Color,
+    },
+    /// A disc emitting to the side its normal points to.
+    Disc {
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        emission: Color,
+    },
+    /// The disc of the sun infinitely far away towards `direction`, covering the cone of
+    /// directions whose cosine to it is at least `cos_max`.
+    Sun {
+        direction: Vec3,
+        radiance: Color,
+        cos_max: f64,
+    },
+}
+
+impl Light {
+    pub fn spot(position: Point3, direction: Vec3, intensity: Color, cone_angle: f64, falloff: f64) -> Light {
+        return Light::Spot {
+            position,
+            direction: direction.unit_vector(),
+            intensity,
+            cos_cone: deg_to_rad(cone_angle).cos(),
+            cos_falloff_start: deg_to_rad((cone_angle - falloff).max(0.0)).cos(),
+        };
+    }
+}
+
+impl LightTrait for Light {
+    fn sample(&self, point: &Point3, u: (f64, f64)) -> Option<LightSample> {
+        return match self {
+            Light::Sphere { sphere } => sample_sphere(sphere, point, u),
+            Light::Triangle { triangle } => sample_triangle(triangle, point, u),
+            Light::Point { position, intensity } => Some(sample_point(position, point, *intensity)),
+            Light::Spot { position, direction, intensity, cos_cone, cos_falloff_start } => {
+                let mut sample = sample_point(position, point, *intensity);
+                let cos_theta = dot(&-sample.direction, direction);
+                sample.radiance = sample.radiance * smoothstep(*cos_cone, *cos_falloff_start, cos_theta);
+                Some(sample)
+            }
+            Light::Directional { direction, irradiance } => Some(LightSample {
+                direction: -direction.unit_vector(),
+                distance: INFINITY,
+                radiance: *irradiance,
+                pdf: 1.0,
+                delta: true,
+            }),
+            Light::Rect { corner, edge_u, edge_v, emission } => {
+                let on_light = *corner + *edge_u * u.0 + *edge_v * u.1;
+                let normal = cross(edge_u, edge_v);
+                sample_area(point, on_light, &normal, normal.length(), *emission, true)
+            }
+            Light::Disc { center, normal, radius, emission } => {
+                let onb = Onb::from_w(normal);
+                let r = radius * u.0.sqrt();
+                let phi = 2.0 * PI * u.1;
+                let on_light = *center + onb.local(r * phi.cos(), r * phi.sin(), 0.0);
+                sample_area(point, on_light, normal, PI * radius * radius, *emission, true)
+            }
+            Light::Sun { direction, radiance, cos_max } => {
+                let onb = Onb::from_w(direction);
+                let cos_theta = 1.0 - u.0 * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * u.1;
+                Some(LightSample {
+                    direction: onb.local(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta),
+                    distance: INFINITY,
+                    radiance: *radiance,
+                    pdf: 1.0 / (2.0 * PI * (1.0 - cos_max)),
+                    delta: false,
+                })
+            }
+        };
+    }
+
+    fn pdf(&self, origin: &Point3, point: &Point3) -> f64 {
+        return match self {
+            Light::Sphere { sphere } => sphere_cone(sphere, origin).map_or(0.0, |cos_max| {
+                1.0 / (2.0 * PI * (1.0 - cos_max))
+            }),
+            Light::Triangle { triangle } => area_pdf(origin, point, &triangle.n, triangle.area()),
+            Light::Point { .. } | Light::Spot { .. } | Light::Directional { .. } => 0.0,
+            Light::Rect { edge_u, edge_v, .. } => {
+                let normal = cross(edge_u, edge_v);
+                area_pdf(origin, point, &normal, normal.length())
+            }
+            Light::Disc { normal, radius, .. } => area_pdf(origin, point, normal, PI * radius * radius),
+            // Points on the sun are infinitely far away, it is only asked for directions that
+            // `hit` found inside its cone.
+            Light::Sun { cos_max, .. } => 1.0 / (2.0 * PI * (1.0 - cos_max)),
+        };
+    }
+
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Color)> {
+        match self {
+            Light::Rect { corner, edge_u, edge_v, emission } => {
+                let normal = cross(edge_u, edge_v);
+                let t = hit_plane(ray, corner, &normal, t_min, t_max)?;
+                // Express the hit in the coordinates of the edges.
+                let p = ray.at(t) - *corner;
+                let w = normal / normal.length_squared();
+                let a = dot(&w, &cross(&p, edge_v));
+                let b = dot(&w, &cross(edge_u, &p));
+                if a < 0.0 || a > 1.0 || b < 0.0 || b > 1.0 {
+                    return None;
+                }
+                return Some((t, emitted_towards(&normal, ray, *emission)));
+            }
+            Light::Disc { center, normal, radius, emission } => {
+                let t = hit_plane(ray, center, normal, t_min, t_max)?;
+                if (ray.at(t) - *center).length_squared() > radius * radius {
+                    return None;
+                }
+                return Some((t, emitted_towards(normal, ray, *emission)));
+            }
+            Light::Sun { direction, radiance, cos_max } => {
+                // Only rays that leave the scene reach the sun.
+                if t_max < INFINITY || dot(&ray.direction.unit_vector(), direction) < *cos_max {
+                    return None;
+                }
+                return Some((INFINITY, *radiance));
+            }
+            _ => None,
+        }
+    }
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    if edge1 <= edge0 {
+        return if x >= edge0 { 1.0 } else { 0.0 };
+    }
+    let t = clamp((x - edge0) / (edge1 - edge0), 0.0, 1.0);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+fn sample_point(position: &Point3, point: &Point3, intensity: Color) -> LightSample {
+    let to_light = *position - *point;
+    let distance_squared = to_light.length_squared();
+    let distance = distance_squared.sqrt();
+    return LightSample {
+        direction: to_light / distance,
+        distance,
+        radiance: intensity / distance_squared,
+        pdf: 1.0,
+        delta: true,
+    };
+}
+
+/// Converts a point sampled uniformly on the area of a light to a direction from `point`.
+fn sample_area(point: &Point3, on_light: Point3, normal: &Vec3, area: f64, emission: Color, one_sided: bool) -> Option<LightSample> {
+    let to_light = on_light - *point;
+    let distance_squared = to_light.length_squared();
+    let distance = distance_squared.sqrt();
+    let direction = to_light / distance;
+    let cosine = dot(&normal.unit_vector(), &direction);
+    if cosine.abs() < 1e-8 || (one_sided && cosine > 0.0) {
+        return None;
+    }
+    return Some(LightSample {
+        direction,
+        distance,
+        radiance: emission,
+        pdf: distance_squared / (cosine.abs() * area),
+        delta: false,
+    });
+}
+
+fn area_pdf(origin: &Point3, point: &Point3, normal: &Vec3, area: f64) -> f64 {
+    let to_light = *point - *origin;
+    let distance_squared = to_light.length_squared();
+    let cosine = dot(&normal.unit_vector(), &to_light).abs() / distance_squared.sqrt();
+    if cosine < 1e-8 {
+        return 0.0;
+    }
+    return distance_squared / (cosine * area);
+}
+
+fn hit_plane(ray: &Ray, point: &Point3, normal: &Vec3, t_min: f64, t_max: f64) -> Option<f64> {
+    let denominator = dot(normal, &ray.direction);
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+    let t = dot(normal, &(*point - ray.origin)) / denominator;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    return Some(t);
+}
+
+/// One-sided lights only emit into the half space their normal points to.
+fn emitted_towards(normal: &Vec3, ray: &Ray, emission: Color) -> Color {
+    return if dot(normal, &ray.direction) < 0.0 { emission } else { Vec3::new() };
+}
+
+/// The cosine of the half angle of the cone the sphere covers as seen from `point`, or `None` if
+/// the point lies inside the sphere.
+fn sphere_cone(sphere: &Sphere, point: &Point3) -> Option<f64> {
+    let distance_squared = (sphere.position - *point).length_squared();
+    let radius_squared = sphere.radius * sphere.radius;
+    if distance_squared <= radius_squared {
+        return None;
+    }
+    return Some((1.0 - radius_squared / distance_squared).sqrt());
+}
+
+fn sample_sphere(sphere: &Sphere, point: &Point3, u: (f64, f64)) -> Option<LightSample> {
+    // Sample the cone of directions that hit the sphere uniformly.
+    let cos_max = sphere_cone(sphere, point)?;
+    let to_center = sphere.position - *point;
+    let onb = Onb::from_w(&to_center);
+
+    let cos_theta = 1.0 - u.0 * (1.0 - cos_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    let direction = onb.local(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+    let b = dot(&to_center, &direction);
+    let discriminant = sphere.radius * sphere.radius - (to_center.length_squared() - b * b);
+    return Some(LightSample {
+        direction,
+        distance: b - discriminant.max(0.0).sqrt(),
+        radiance: sphere.material.emission(),
+        pdf: 1.0 / (2.0 * PI * (1.0 - cos_max)),
+        delta: false,
+    });
+}
+
+fn sample_triangle(triangle: &Triangle, point: &Point3, u: (f64, f64)) -> Option<LightSample> {
+    // Sample the area of the triangle uniformly, emissive triangles emit to both sides.
+    let su = u.0.sqrt();
+    let b0 = 1.0 - su;
+    let b1 = u.1 * su;
+    let on_light = triangle.a * b0 + triangle.b * b1 + triangle.c * (1.0 - b0 - b1);
+    return sample_area(point, on_light, &triangle.n, triangle.area(), triangle.texture.emission(), false);
+}
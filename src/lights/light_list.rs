@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::f64::INFINITY;
+use std::sync::Arc;
+
+use crate::hittables::hittable::{HitRecord, Hittable, object_id};
+use crate::lights::environment::{Background, EnvironmentMap};
+use crate::lights::light::{Light, LightSample, LightTrait};
+use crate::ray::Ray;
+use crate::samplers::sampler::Sampler;
+use crate::vec3::{Color, Point3, Vec3};
+
+/// A hit of a ray with a light that is not part of the scene geometry.
+pub struct LightHit {
+    pub t: f64,
+    pub radiance: Color,
+    /// The density with which light sampling picks the direction of the ray.
+    pub pdf: f64,
+}
+
+/// All lights of a scene. One light is chosen uniformly for every sample.
+pub struct LightList {
+    lights: Vec<Light>,
+    /// Maps the object id of an emissive primitive to its index in `lights`.
+    indices: HashMap<usize, usize>,
+    /// The indices of the lights that rays can hit without being part of the geometry.
+    area_lights: Vec<usize>,
+    /// An image background, which is sampled like any other light.
+    environment: Option<Arc<EnvironmentMap>>,
+}
+
+impl LightList {
+    /// Collects the primitives with an emissive material and adds the lights and the environment
+    /// map of the scene.
+    pub fn new(hittables: &Vec<Arc<Hittable>>, lights: &Vec<Light>, background: &Background) -> LightList {
+        let mut list = LightList {
+            lights: Vec::new(),
+            indices: HashMap::new(),
+            area_lights: Vec::new(),
+            environment: match background {
+                Background::Map { map } => Some(map.clone()),
+                _ => None,
+            },
+        };
+        for hittable in hittables.iter() {
+            list.collect(hittable);
+        }
+        let sun = match background {
+            Background::Sky { sky } => Some(sky.sun()),
+            _ => None,
+        };
+        for light in lights.iter().chain(sun.iter()) {
+            if let Light::Rect { .. } | Light::Disc { .. } | Light::Sun { .. } = light {
+                list.area_lights.push(list.lights.len());
+            }
+            list.lights.push(light.clone());
+        }
+        return list;
+    }
+
+    fn collect(&mut self, hittable: &Hittable) {
+        match hittable {
+            Hittable::Sphere { sphere } => {
+                if sphere.material.is_emissive() {
+                    self.indices.insert(object_id(sphere), self.lights.len());
+                    self.lights.push(Light::Sphere { sphere: sphere.clone() });
+                }
+            }
+            Hittable::Triangle { triangle } => {
+                if triangle.texture.is_emissive() {
+                    self.indices.insert(object_id(triangle), self.lights.len());
+                    self.lights.push(Light::Triangle { triangle: triangle.clone() });
+                }
+            }
+            Hittable::Bvh { bvh } => {
+                self.collect(&bvh.left);
+                self.collect(&bvh.right);
+            }
+            Hittable::HittableList { hittable_list } => {
+                for hittable in hittable_list.list.iter() {
+                    self.collect(hittable);
+                }
+            }
+            // Light sampling assumes lights stand still, moving emitters are only found by
+            // following the materials.
+            Hittable::Moving { .. } => {}
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.lights.len() + self.environment.is_some() as usize;
+    }
+
+    /// Chooses a light and samples a direction towards it. The density of the sample includes the
+    /// probability of choosing the light.
+    pub fn sample(&self, point: &Point3, sampler: &mut Sampler) -> Option<LightSample> {
+        let count = self.len();
+        if count == 0 {
+            return None;
+        }
+        let index = ((sampler.get_1d() * count as f64) as usize).min(count - 1);
+        let u = sampler.get_2d();
+        let mut sample = match self.lights.get(index) {
+            Some(light) => light.sample(point, u)?,
+            None => {
+                let (direction, radiance, pdf) = self.environment.as_ref()?.sample(u)?;
+                LightSample {
+                    direction,
+                    distance: INFINITY,
+                    radiance,
+                    pdf,
+                    delta: false,
+                }
+            }
+        };
+        sample.pdf /= count as f64;
+        return Some(sample);
+    }
+
+    /// The density with which `sample` produces the direction from `origin` to the hit `rec`, or
+    /// zero if the hit primitive is not a light.
+    pub fn pdf(&self, origin: &Point3, rec: &HitRecord) -> f64 {
+        return match self.indices.get(&rec.object) {
+            Some(index) => self.lights[*index].pdf(origin, &rec.point) / self.len() as f64,
+            None => 0.0,
+        };
+    }
+
+    /// The density with which `sample` picks `direction` towards the environment map.
+    pub fn environment_pdf(&self, direction: &Vec3) -> f64 {
+        return match &self.environment {
+            Some(map) => map.pdf(direction) / self.len() as f64,
+            None => 0.0,
+        };
+    }
+
+    /// Finds the closest area light along the ray.
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<LightHit> {
+        let mut closest: Option<(usize, f64, Color)> = None;
+        let mut closest_so_far = t_max;
+        for index in self.area_lights.iter() {
+            if let Some((t, radiance)) = self.lights[*index].hit(ray, t_min, closest_so_far) {
+                closest_so_far = t;
+                closest = Some((*index, t, radiance));
+            }
+        }
+        let (index, t, radiance) = closest?;
+        return Some(LightHit {
+            t,
+            radiance,
+            pdf: self.lights[index].pdf(&ray.origin, &ray.at(t)) / self.len() as f64,
+        });
+    }
+}
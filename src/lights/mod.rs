@@ -0,0 +1,4 @@
+pub mod environment;
+pub mod light;
+pub mod light_list;
+pub mod sky;
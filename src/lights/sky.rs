@@ -0,0 +1,160 @@
+use std::f64::consts::PI;
+
+use crate::lights::light::Light;
+use crate::utils::math_utils::deg_to_rad;
+use crate::vec3::{Color, dot, Vec3};
+
+/// Converts the luminance of the model in kcd/m² to the radiance used by the renderer, so a clear
+/// sky is about as bright as the default gradient.
+const SKY_SCALE: f64 = 0.06;
+
+/// The luminance of the sun outside of the atmosphere in kcd/m².
+const SUN_LUMINANCE: f64 = 1.6e6;
+
+/// The analytic daylight model by Preetham, Shirley and Smits ("A Practical Analytic Model for
+/// Daylight", 1999). The sky is evaluated per direction; the sun disk is a separate light so it
+/// can be sampled directly.
+pub struct Sky {
+    /// The unit direction towards the sun.
+    pub sun_direction: Vec3,
+    pub turbidity: f64,
+    pub ground_albedo: Color,
+    pub intensity: f64,
+    /// The cosine of the angular radius of the sun disk.
+    pub sun_cos_max: f64,
+    pub sun_radiance: Color,
+    /// The radiance reflected by the ground, which is lit by the sky and the sun.
+    ground: Color,
+    /// The Perez coefficients A to E for the luminance and the two chromaticity coordinates.
+    perez: [[f64; 5]; 3],
+    /// The values at the zenith divided by the Perez function for the zenith.
+    zenith: [f64; 3],
+}
+
+impl Sky {
+    /// `sun_size` is the angular diameter of the sun disk in degrees. The sun has to be above the
+    /// horizon.
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color, intensity: f64, sun_size: f64) -> Sky {
+        let sun_direction = sun_direction.unit_vector();
+        let theta_s = sun_direction.y().max(0.0).acos();
+        let t = turbidity;
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let theta = [theta_s * theta_s * theta_s, theta_s * theta_s, theta_s, 1.0];
+        let zenith_x = t * t * dot4(&[0.00166, -0.00375, 0.00209, 0.0], &theta)
+            + t * dot4(&[-0.02903, 0.06377, -0.03202, 0.00394], &theta)
+            + dot4(&[0.11693, -0.21196, 0.06052, 0.25886], &theta);
+        let zenith_yc = t * t * dot4(&[0.00275, -0.00610, 0.00317, 0.0], &theta)
+            + t * dot4(&[-0.04214, 0.08970, -0.04153, 0.00516], &theta)
+            + dot4(&[0.15346, -0.26756, 0.06670, 0.26688], &theta);
+        let zenith = [zenith_y, zenith_x, zenith_yc];
+        let mut normalized = [0.0; 3];
+        for i in 0..3 {
+            normalized[i] = zenith[i] / perez_function(&perez[i], 0.0, theta_s);
+        }
+
+        let sun_cos_max = deg_to_rad(sun_size / 2.0).cos();
+        let sun_radiance = sun_transmittance(theta_s, turbidity) * SUN_LUMINANCE * SKY_SCALE * intensity;
+        let mut sky = Sky {
+            sun_direction,
+            turbidity,
+            ground_albedo,
+            intensity,
+            sun_cos_max,
+            sun_radiance,
+            ground: Vec3::new(),
+            perez,
+            zenith: normalized,
+        };
+        let sun_solid_angle = 2.0 * PI * (1.0 - sun_cos_max);
+        let irradiance = sky.sky_irradiance() + sun_radiance * sun_solid_angle * sun_direction.y().max(0.0);
+        sky.ground = ground_albedo * irradiance / PI;
+        return sky;
+    }
+
+    /// The radiance arriving from `direction`, without the sun disk. Directions below the horizon
+    /// see the ground.
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        let direction = direction.unit_vector();
+        if direction.y() < 0.0 {
+            return self.ground;
+        }
+        let cos_theta = direction.y().max(1e-3);
+        let gamma = dot(&direction, &self.sun_direction).min(1.0).acos();
+        let mut values = [0.0; 3];
+        for i in 0..3 {
+            values[i] = self.zenith[i] * perez_function(&self.perez[i], cos_theta.acos(), gamma);
+        }
+        return xyy_to_rgb(values[1], values[2], values[0]) * SKY_SCALE * self.intensity;
+    }
+
+    /// The light for the sun disk.
+    pub fn sun(&self) -> Light {
+        return Light::Sun {
+            direction: self.sun_direction,
+            radiance: self.sun_radiance,
+            cos_max: self.sun_cos_max,
+        };
+    }
+
+    /// Integrates the sky over the upper hemisphere, weighted by the cosine to the up axis.
+    fn sky_irradiance(&self) -> Color {
+        let (n_theta, n_phi) = (32, 64);
+        let d_theta = PI / 2.0 / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut irradiance = Vec3::new();
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let direction = Vec3::spherical_coordinate(theta, phi);
+                irradiance = irradiance + self.radiance(&direction) * (theta.cos() * theta.sin() * d_theta * d_phi);
+            }
+        }
+        return irradiance;
+    }
+}
+
+fn dot4(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    return a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3];
+}
+
+/// The distribution of a sky value over the angle `theta` to the zenith and the angle `gamma` to
+/// the sun.
+fn perez_function(c: &[f64; 5], theta: f64, gamma: f64) -> f64 {
+    let cos_gamma = gamma.cos();
+    return (1.0 + c[0] * (c[1] / theta.cos()).exp()) * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * cos_gamma * cos_gamma);
+}
+
+/// Converts a CIE xyY color to linear sRGB.
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 {
+        return Vec3::new();
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    let r = 3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z;
+    let g = -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z;
+    let b = 0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z;
+    return Color { e: [r.max(0.0), g.max(0.0), b.max(0.0)] };
+}
+
+/// The fraction of sunlight passing through the atmosphere at red, green and blue wavelengths,
+/// following the Rayleigh and aerosol terms of the appendix of the paper.
+fn sun_transmittance(theta_s: f64, turbidity: f64) -> Color {
+    let relative_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let mut transmittance = [0.0; 3];
+    for (i, lambda) in [0.680f64, 0.550, 0.440].iter().enumerate() {
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * relative_mass).exp();
+        let aerosol = (-beta * lambda.powf(-1.3) * relative_mass).exp();
+        transmittance[i] = rayleigh * aerosol;
+    }
+    return Color { e: transmittance };
+}
@@ -1,170 +1,192 @@
-extern crate sdl2;
-
-use std::f64::INFINITY;
-use std::fs::File;
-use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::{Arc, Mutex};
-use std::thread;
 use std::time::Instant;
 
-use threadpool::ThreadPool;
-
-use utils::math_utils::random_double;
-
-use crate::camera::{Camera, create_camera};
-use crate::color::write_color;
-use crate::hittables::hittable::{Hittable, HittableTrait};
-use crate::material::Material;
-use crate::optimizations::bvh::Bvh;
-use crate::parsers::from_stl::read_stl;
-use crate::ray::Ray;
-use crate::textures::texture::Texture;
-use crate::vec3::{Color, create_vec_3, Vec3};
-use crate::parsers::obj::read_obj;
-use crate::hittables::sphere::Sphere;
-use std::borrow::{Borrow, BorrowMut};
-use crate::opengl::opengl::draw_window;
-
-mod opengl;
-
-
-mod camera;
-mod color;
-mod hittables;
-pub mod material;
-mod noises;
-mod optimizations;
-mod parsers;
-mod ray;
-mod textures;
-mod utils;
-mod vec3;
-
-
-const ASPECT_RATIO: f64 = 16.0 / 9.0;
-const WIDTH: i32 = 500;
-const HEIGHT: i32 = (WIDTH as f64 / ASPECT_RATIO) as i32;
-const SAMPLES_PER_PIXEL: usize = 200;
-const DEPTH: i32 = 100;
-
-fn color_at(r: &Ray, world: Arc<Hittable>, depth: i32) -> Color {
-    if depth == 0 {
-        return Color { e: [0.0, 0.0, 0.0] };
-    }
+use clap::Parser;
 
-    let option = world.clone().hit(r, 0.0001, INFINITY);
-    if !option.is_none() {
-        let rec = option.unwrap();
-        return rec
-            .material
-            .scatter(r, &rec, depth, world.clone())
-            .unwrap_or(Color { e: [0.0, 0.0, 0.0] });
-    }
+use raytracer::aov::Aov;
+use raytracer::color::ToneMapping;
+use raytracer::hittables::hittable::Hittable;
+use raytracer::image::{BitDepth, Image, ImageFormat};
+use raytracer::lights::light_list::LightList;
+use raytracer::optimizations::bvh::surround;
+use raytracer::parsers::scene::load_scene;
+use raytracer::checkpoint::{read_checkpoint, write_checkpoint};
+use raytracer::renderer::{
+    build_world, denoise, new_framebuffer, render_aovs, render_passes, resolve_framebuffer, Framebuffer,
+};
+use raytracer::settings::RenderSettings;
+use raytracer::Scene;
 
-    let unit_direction = r.direction.unit_vector();
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    return Color { e: [1.0, 1.0, 1.0] } * (1.0 - t) + Color { e: [0.5, 0.7, 1.0] } * t;
-    // Vec3::new()
-}
+use crate::cli::{Cli, Command, RenderArgs};
 
-fn main() {
-    let look_from = create_vec_3(0.0, 0.0, 3.0);
-    let look_at = create_vec_3(0.0, 0.0, 0.0);
-    let vup = create_vec_3(0.0, 1.0, 0.0);
+mod cli;
 
-    let cam = create_camera(look_from, look_at, vup, 100.0, ASPECT_RATIO, 2.0);
-
-    let mut vec = vec![Arc::from(Hittable::Sphere {
-        sphere: Sphere {
-            position: Vec3 { e: [20.0, 60.0, 15.0] },
-            radius: 10.0,
-            material: Arc::new(Material::Diffuse { albedo: Texture::Solid { color: Vec3::new() }, emission: Vec3 { e: [100.0, 100.0, 100.0] } })
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Render(args) => {
+            // Check the output formats before spending time on the render.
+            check_output(&args.output, ImageFormat::from_path(&args.output).is_some(), ".ppm, .png, .pfm, .hdr or .exr");
+            if let Some(hdr_output) = &args.hdr_output {
+                let format = ImageFormat::from_path(hdr_output);
+                check_output(hdr_output, format.map_or(false, |f| f.is_high_dynamic_range()), ".pfm, .hdr or .exr");
+            }
+            if let Some(heatmap) = &args.sample_heatmap {
+                check_output(heatmap, ImageFormat::from_path(heatmap).is_some(), ".ppm, .png, .pfm, .hdr or .exr");
+            }
+            let mut scene = open_scene(&args.scene);
+            args.apply(&mut scene.settings);
+            let world = build_world(&scene);
+            let mut image = render_scene(&scene, &world, scene.settings, &args);
+            if scene.settings.denoise {
+                image = denoise(&scene, &world, &scene.settings, &image);
+            }
+            let bit_depth = args.bit_depth.bit_depth();
+            write_data(&image, &args.output, bit_depth, &scene.settings.tone_mapping);
+            if let Some(hdr_output) = &args.hdr_output {
+                write_data(&image, hdr_output, bit_depth, &scene.settings.tone_mapping);
+            }
+            if !args.aovs.is_empty() {
+                let aovs: Vec<Aov> = args.aovs.iter().map(|aov| aov.aov()).collect();
+                let images = render_aovs(&scene, &world, &scene.settings, &aovs);
+                for (aov, image) in aovs.iter().zip(images.iter()) {
+                    write_data(image, &aov_path(&args.output, *aov), bit_depth, &scene.settings.tone_mapping);
+                }
+            }
+        }
+        Command::Info { scene: path } => {
+            let scene = open_scene(&path);
+            print_info(&path, &scene);
         }
-    })];
-    for i in 0..5{
-        vec.push(Arc::new(Hittable::Sphere { sphere: Sphere {
-            position: Vec3 { e: [i as f64 * 1.2 - 2.5, 0.0, 0.0] },
-            radius: 0.5,
-            material: Arc::new(Material::CookTorrance {
-                diffuse: Texture::Solid {color: Vec3{ e: [1.0, 0.0, 0.0] }},
-                specular: Texture::Solid {color: Vec3{ e: [1.0, 1.0, 1.0] }},
-                roughness: 0.3,
-                k_d: i as f64 / 5.0,
-                emission: Vec3::new()
-            })
-        } }));
     }
+}
 
+/// Places the AOV next to the output, e.g. `render.png` becomes `render.albedo.exr`.
+fn aov_path(output: &Path, aov: Aov) -> PathBuf {
+    let stem = output.file_stem().map_or(String::from("output"), |stem| stem.to_string_lossy().into_owned());
+    return output.with_file_name(format!("{}.{}.exr", stem, aov.name()));
+}
 
+fn check_output(path: &Path, supported: bool, expected: &str) {
+    if !supported {
+        eprintln!("Cannot write {}: unsupported image format, expected {}", path.display(), expected);
+        process::exit(1);
+    }
+}
 
-    let world_box = Bvh::new_morton(&mut vec);
-
-    render_scene_to_file(cam, world_box);
+fn open_scene(path: &Path) -> Scene {
+    return match load_scene(path) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
 }
 
+fn print_info(path: &Path, scene: &Scene) {
+    let settings = scene.settings;
+    let bounds = surround(&scene.hittables);
+    println!("Scene:             {}", path.display());
+    println!("Resolution:        {}x{}", settings.width, settings.height);
+    println!("Samples per pixel: {}", settings.samples_per_pixel);
+    println!("Maximum depth:     {}", settings.depth);
+    if settings.russian_roulette {
+        println!("Russian roulette:  after {} bounces", settings.min_bounces);
+    } else {
+        println!("Russian roulette:  off");
+    }
+    println!("Primitives:        {}", scene.hittables.len());
+    println!("Lights:            {}", LightList::new(&scene.hittables, &scene.lights, &scene.background).len());
+    println!("Bounds:            {} - {}", bounds.bounds[0], bounds.bounds[1]);
+}
 
-fn render_scene_to_file(cam: Camera, world_box: Arc<Hittable>) {
-    let n_workers = 11;
-    let pool = ThreadPool::new(n_workers);
 
+fn render_scene(scene: &Scene, world: &Arc<Hittable>, settings: RenderSettings, args: &RenderArgs) -> Image {
     let before_render = Instant::now();
-    let mut result = initiate_result_mutex(WIDTH, HEIGHT);
-    launch_window_thread(&mut result);
-
-    for job in 0..HEIGHT {
-        let world_box = world_box.clone();
-        let result = result.clone();
-        pool.execute(move || {
-            let mut row = Vec::new();
-            let x = HEIGHT - job - 1;
-            println!("Current line: {}", x);
-            for j in 0..WIDTH {
-                let mut pixel_color = Color { e: [0.0, 0.0, 0.0] };
-                for _ in 0..SAMPLES_PER_PIXEL {
-                    let u = (j as f64 + random_double(0.0, 1.0)) / (WIDTH as f64 - 1.0);
-                    let v = (x as f64 + random_double(0.0, 1.0)) / (HEIGHT as f64 - 1.0);
-                    let r = cam.get_ray(u, v);
-
-                    pixel_color = pixel_color + color_at(&r, world_box.clone(), DEPTH);
-                }
-                row.push(pixel_color);
+    let result = match &args.checkpoint {
+        Some(path) if args.resume && path.exists() => resume_checkpoint(path, &settings),
+        _ => new_framebuffer(settings.width, settings.height),
+    };
+    if !args.no_preview {
+        launch_window_thread(&result, settings);
+    }
+
+    let mut last_checkpoint = Instant::now();
+    print_progress(&result, &settings);
+    render_passes(scene, world, &settings, &result, &mut |framebuffer| {
+        print_progress(framebuffer, &settings);
+        if let Some(path) = &args.checkpoint {
+            if last_checkpoint.elapsed().as_secs() >= args.checkpoint_interval {
+                save_checkpoint(framebuffer, &settings, path);
+                last_checkpoint = Instant::now();
             }
-            result.lock().unwrap()[job as usize] = row;
-        });
+        }
+    });
+    if let Some(path) = &args.checkpoint {
+        save_checkpoint(&result, &settings, path);
     }
-    pool.join();
 
-    write_data(&mut result);
     let time_2 = before_render.elapsed().as_secs();
     println!("Time for render: {}", time_2);
 
     println!("Finished!");
+    if let Some(path) = &args.sample_heatmap {
+        let heatmap = result.lock().unwrap().heatmap(settings.samples_per_pixel);
+        write_data(&heatmap, path, BitDepth::Eight, &ToneMapping::default());
+    }
+    return resolve_framebuffer(&result);
 }
 
-fn initiate_result_mutex(width: i32, height: i32) -> Arc<Mutex<Vec<Vec<Vec3>>>> {
-    let result = Arc::new(Mutex::new(Vec::new()));
-    for i in 0..height {
-        result.lock().unwrap().push(Vec::new());
-        for _ in 0..width {
-            result.lock().unwrap()[i as usize].push(Vec3::new());
+fn print_progress(framebuffer: &Framebuffer, settings: &RenderSettings) {
+    let (done, remaining) = framebuffer.lock().unwrap().progress(settings);
+    if remaining > 0 {
+        println!("Samples per pixel: {}/{}, {} pixels left", done, settings.samples_per_pixel, remaining);
+    }
+}
+
+fn resume_checkpoint(path: &Path, settings: &RenderSettings) -> Framebuffer {
+    let checkpoint = match read_checkpoint(path) {
+        Ok(checkpoint) => checkpoint,
+        Err(e) => {
+            eprintln!("Could not read the checkpoint {}: {}", path.display(), e);
+            process::exit(1);
         }
+    };
+    if let Err(difference) = checkpoint.check(settings) {
+        eprintln!("The checkpoint {} {}", path.display(), difference);
+        process::exit(1);
     }
-    result
+    let accumulation = checkpoint.accumulation;
+    println!("Resuming with {} samples per pixel", accumulation.min_samples());
+    return Arc::new(Mutex::new(accumulation));
 }
 
-fn launch_window_thread(result: &mut Arc<Mutex<Vec<Vec<Vec3>>>>) {
+fn save_checkpoint(framebuffer: &Framebuffer, settings: &RenderSettings, path: &Path) {
+    let accumulation = framebuffer.lock().unwrap().clone();
+    if let Err(e) = write_checkpoint(&accumulation, settings, path) {
+        eprintln!("Could not write the checkpoint {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(feature = "preview")]
+fn launch_window_thread(result: &Framebuffer, settings: RenderSettings) {
     let result = result.clone();
-    thread::spawn(move || draw_window(result.clone(), WIDTH, HEIGHT, SAMPLES_PER_PIXEL));
+    std::thread::spawn(move || {
+        raytracer::opengl::opengl::draw_window(result, settings.width, settings.height, settings.tone_mapping)
+    });
 }
 
-fn write_data(result: &mut Arc<Mutex<Vec<Vec<Vec3>>>>) {
-    let mut file =
-        File::create("resources/renders/output.ppm").expect("Could not open the output file.");
-    writeln!(file, "P3\n{} {}\n255\n", WIDTH, HEIGHT).expect("Cannot write to file.");
-    let x = result.lock().unwrap();
-    for i in 0..HEIGHT as usize {
-        for j in 0..WIDTH as usize {
-            write_color(&mut file, x[i][j], SAMPLES_PER_PIXEL);
-        }
+#[cfg(not(feature = "preview"))]
+fn launch_window_thread(_result: &Framebuffer, _settings: RenderSettings) {
+    eprintln!("The preview window is not available, the raytracer was built without the `preview` feature.");
+}
+
+fn write_data(image: &Image, output: &Path, bit_depth: BitDepth, tone_mapping: &ToneMapping) {
+    if let Err(e) = image.save(output, bit_depth, tone_mapping) {
+        eprintln!("Could not write {}: {}", output.display(), e);
+        process::exit(1);
     }
 }
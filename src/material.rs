@@ -1,21 +1,41 @@
 use std::f64::consts::PI;
-use std::sync::Arc;
 
-use crate::color_at;
-use crate::hittables::hittable::{HitRecord, Hittable};
-use crate::ray::Ray;
+use crate::hittables::hittable::HitRecord;
+use crate::microfacet::Ggx;
+use crate::samplers::sampler::Sampler;
+use crate::samplers::warp::{sample_ball, sample_cosine_hemisphere};
 use crate::textures::texture::Texture;
-use crate::utils::math_utils::random_double;
-use crate::vec3::{Color, dot, random_in_hemisphere, random_in_unit_sphere, reflect, refract};
-
-pub(crate) trait MaterialTrait: Send + Sync {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        depth: i32,
-        world: Arc<Hittable>,
-    ) -> Option<Color>;
+use crate::vec3::{Color, dot, Vec3, reflect, refract};
+
+/// A direction chosen by [`MaterialTrait::sample`], in the local shading frame of the hit.
+pub struct BsdfSample {
+    /// The direction the light arrives from, which the path continues in.
+    pub w_i: Vec3,
+    /// The BSDF times the cosine of `w_i` divided by `pdf`, which the throughput is multiplied with.
+    pub attenuation: Color,
+    /// The solid angle density the direction was sampled with.
+    pub pdf: f64,
+    /// Whether the direction was chosen deterministically, e.g. by a mirror. Such directions
+    /// cannot be found by light sampling, so `eval` and `pdf` are zero for them.
+    pub specular: bool,
+}
+
+/// The scattering of light at a surface. Directions are unit vectors in the local shading frame of
+/// the hit, in which the normal is the z axis, and point away from the surface: `w_o` towards
+/// where the light leaves to and `w_i` towards where it arrives from.
+pub trait MaterialTrait: Send + Sync {
+    /// Chooses `w_i` for the given `w_o`, using the values of the sampler.
+    fn sample(&self, w_o: &Vec3, rec: &HitRecord, sampler: &mut Sampler) -> Option<BsdfSample>;
+    /// The BSDF for light arriving from `w_i` and leaving towards `w_o`, multiplied by the cosine
+    /// between `w_i` and the normal.
+    fn eval(&self, w_o: &Vec3, w_i: &Vec3, rec: &HitRecord) -> Color;
+    /// The density with which `sample` chooses `w_i`.
+    fn pdf(&self, w_o: &Vec3, w_i: &Vec3, rec: &HitRecord) -> f64;
+    /// Whether `sample` only chooses specular directions, so sampling the lights is pointless.
+    fn is_specular(&self) -> bool;
+    fn emitted(&self, rec: &HitRecord) -> Color;
+    /// The color of the surface, independent of the lighting.
+    fn albedo(&self, rec: &HitRecord) -> Color;
 }
 
 pub enum Material {
@@ -33,6 +53,8 @@ pub enum Material {
         albedo: Texture,
         emission: Color,
     },
+    /// A mix of a diffuse and a GGX microfacet lobe. `k_d` is the share of the diffuse lobe,
+    /// `specular` the reflectance at normal incidence and `roughness` the GGX width.
     CookTorrance {
         diffuse: Texture,
 
@@ -43,92 +65,146 @@ pub enum Material {
     },
 }
 
+impl Material {
+    pub fn emission(&self) -> Color {
+        return match self {
+            Material::Dielectric { emission, .. }
+            | Material::Metal { emission, .. }
+            | Material::Diffuse { emission, .. }
+            | Material::CookTorrance { emission, .. } => *emission,
+        };
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        return self.emission().max_component() > 0.0;
+    }
+}
+
 impl MaterialTrait for Material {
-    fn scatter(
-        &self,
-        w_o: &Ray,
-        rec: &HitRecord,
-        depth: i32,
-        world: Arc<Hittable>,
-    ) -> Option<Color> {
+    fn sample(&self, w_o: &Vec3, rec: &HitRecord, sampler: &mut Sampler) -> Option<BsdfSample> {
         match self {
-            Material::Dielectric { ir, tint, emission } => {
+            Material::Dielectric { ir, tint, .. } => {
                 let refraction_ratio = if rec.front_face { 1.0 / *ir } else { *ir };
 
-                let unit_direction = w_o.direction.unit_vector();
-                let cos_theta = dot(&-unit_direction, &rec.normal).min(1.0);
+                let cos_theta = w_o.z().min(1.0);
                 let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
                 let cannot_refract = refraction_ratio * sin_theta > 1.0;
-                let direction = if cannot_refract
-                    || schlicks(cos_theta, refraction_ratio) > random_double(0.0, 1.0)
-                {
-                    reflect(&unit_direction, &rec.normal)
+                let w_i = if cannot_refract || schlicks(cos_theta, refraction_ratio) > sampler.get_1d() {
+                    reflect(&-*w_o, &NORMAL)
                 } else {
-                    refract(&unit_direction, &rec.normal, refraction_ratio)
+                    refract(&-*w_o, &NORMAL, refraction_ratio).unit_vector()
                 };
-                return Some(
-                    *emission
-                        + tint.value_at(rec.u, rec.v, rec.point)
-                        * color_at(&Ray::new(rec.point, direction), world.clone(), depth - 1),
-                );
+                return Some(BsdfSample {
+                    w_i,
+                    attenuation: tint.value_at(rec.u, rec.v, rec.point),
+                    pdf: 0.0,
+                    specular: true,
+                });
             }
 
-            Material::Metal {
-                albedo,
-                fuzz,
-                emission,
-            } => {
-                let reflected = reflect(&w_o.direction.unit_vector(), &rec.normal);
-
-                let scattered = Ray::new(rec.point, reflected + random_in_unit_sphere() * *fuzz);
-                if dot(&scattered.direction, &rec.normal) > 0.0 {
-                    Some(
-                        *emission
-                            + albedo.value_at(rec.u, rec.v, rec.point)
-                            * color_at(&scattered, world.clone(), depth - 1),
-                    )
+            Material::Metal { albedo, fuzz, .. } => {
+                let u = sampler.get_2d();
+                let w_i = (reflect(&-*w_o, &NORMAL) + sample_ball(u, sampler.get_1d()) * *fuzz).unit_vector();
+                if w_i.z() > 0.0 {
+                    Some(BsdfSample {
+                        w_i,
+                        attenuation: albedo.value_at(rec.u, rec.v, rec.point),
+                        pdf: 0.0,
+                        specular: true,
+                    })
                 } else {
                     None
                 }
             }
 
-            Material::Diffuse { albedo, emission } => {
-                let scatter_dir = random_in_hemisphere(&rec.normal);
-                return Some(
-                    *emission
-                        + (albedo.value_at(rec.u, rec.v, rec.point)
-                        * color_at(
-                        &Ray::new(rec.point, scatter_dir),
-                        world.clone(),
-                        depth - 1,
-                    )
-                        * dot(&scatter_dir.unit_vector(), &rec.normal.unit_vector())
-                        * 2.0),
-                );
+            Material::Diffuse { .. } | Material::CookTorrance { .. } => {
+                if w_o.z() <= 0.0 {
+                    return None;
+                }
+                let u = sampler.get_2d();
+                let w_i = match self {
+                    // Choose one of the lobes by its share, the density below covers both.
+                    Material::CookTorrance { k_d, roughness, .. } if sampler.get_1d() >= *k_d => {
+                        let h = Ggx::new(*roughness).sample_visible_normal(w_o, u);
+                        reflect(&-*w_o, &h)
+                    }
+                    _ => sample_cosine_hemisphere(u),
+                };
+                let pdf = self.pdf(w_o, &w_i, rec);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                return Some(BsdfSample {
+                    w_i,
+                    attenuation: self.eval(w_o, &w_i, rec) / pdf,
+                    pdf,
+                    specular: false,
+                });
+            }
+        }
+    }
+
+    fn eval(&self, w_o: &Vec3, w_i: &Vec3, rec: &HitRecord) -> Color {
+        let cos_i = w_i.z();
+        if cos_i <= 0.0 || w_o.z() <= 0.0 {
+            return Vec3::new();
+        }
+        match self {
+            Material::Dielectric { .. } | Material::Metal { .. } => Vec3::new(),
+            Material::Diffuse { albedo, .. } => albedo.value_at(rec.u, rec.v, rec.point) / PI * cos_i,
+            Material::CookTorrance { diffuse, k_d, specular: specular_color, roughness, .. } => {
+                let ggx = Ggx::new(*roughness);
+                let h = (*w_o + *w_i).unit_vector();
+                let f = schlicks_color(&specular_color.value_at(rec.u, rec.v, rec.point), dot(w_i, &h));
+                // D G F / (4 cos_o cos_i), times cos_i.
+                let specular_part = f * (ggx.d(&h) * ggx.g2(w_o, w_i) / (4.0 * w_o.z()));
+                let diffuse_part = diffuse.value_at(rec.u, rec.v, rec.point) / PI * cos_i;
+                // Both lobes reflect at most all light, so their weighted sum does as well.
+                return diffuse_part * *k_d + specular_part * (1.0 - k_d);
             }
-            Material::CookTorrance { diffuse, k_d, specular: specular_color, roughness, emission } => {
-                let w_i = random_in_hemisphere(&rec.normal);
-                let w_o = w_o.direction * -1.0;
-                let color_at_wi = color_at(&Ray::new(rec.point, w_i), world.clone(),
-                                           depth - 1);
-
-                let h = (w_o + w_i).unit_vector();
-                let wi_dot_h = dot(&w_i, &h);
-                let wo_dot_h = dot(&w_o, &h);
-                let n_dot_h = dot(&rec.normal, &h);
-                let g = ((2.0 * n_dot_h * dot(&rec.normal, &w_o)) / wo_dot_h).min(((2.0 * n_dot_h * dot(&rec.normal, &w_i)) / wo_dot_h)).min(1.0);
-                let f = schlicks_color(&specular_color.value_at(rec.u, rec.v, rec.point), wi_dot_h);
-                let m_sqr = roughness * roughness;
-                let n_dot_h_2 = n_dot_h * n_dot_h;
-                let d = 1.0 / (PI * m_sqr * n_dot_h_2 * n_dot_h_2) * ((n_dot_h_2 - 1.0) / (m_sqr * n_dot_h_2)).exp();
-
-                return Option::from(*emission + diffuse.value_at(rec.u, rec.v, rec.point) / PI * *k_d +  color_at_wi * d * f * g * dot(&rec.normal, &w_o) * PI / 2.0 * (1.0 - k_d));
+        }
+    }
+
+    fn pdf(&self, w_o: &Vec3, w_i: &Vec3, _rec: &HitRecord) -> f64 {
+        if w_i.z() <= 0.0 || w_o.z() <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            Material::Dielectric { .. } | Material::Metal { .. } => 0.0,
+            Material::Diffuse { .. } => w_i.z() / PI,
+            Material::CookTorrance { k_d, roughness, .. } => {
+                // Reflecting at the visible normal `h` maps its density to directions with the
+                // Jacobian 1 / (4 w_o.h).
+                let h = (*w_o + *w_i).unit_vector();
+                let specular_pdf = Ggx::new(*roughness).visible_d(w_o, &h) / (4.0 * dot(w_o, &h));
+                k_d * w_i.z() / PI + (1.0 - k_d) * specular_pdf
             }
         }
     }
+
+    fn is_specular(&self) -> bool {
+        return matches!(self, Material::Dielectric { .. } | Material::Metal { .. });
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        return self.emission();
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color {
+        return match self {
+            Material::Dielectric { tint, .. } => tint.value_at(rec.u, rec.v, rec.point),
+            Material::Metal { albedo, .. } | Material::Diffuse { albedo, .. } => albedo.value_at(rec.u, rec.v, rec.point),
+            Material::CookTorrance { diffuse, k_d, specular, .. } => {
+                diffuse.value_at(rec.u, rec.v, rec.point) * *k_d + specular.value_at(rec.u, rec.v, rec.point) * (1.0 - k_d)
+            }
+        };
+    }
 }
 
+/// The normal in the local shading frame.
+const NORMAL: Vec3 = Vec3 { e: [0.0, 0.0, 1.0] };
+
 fn schlicks_color(color: &Color, wi_dot_h: f64) -> Color {
     *color + (*color * -1.0 + 1.0) * (1.0 - wi_dot_h).powi(5)
 }
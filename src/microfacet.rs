@@ -0,0 +1,79 @@
+use std::f64::consts::PI;
+
+use crate::vec3::{cross, dot, Vec3};
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals with Smith masking and shadowing.
+/// Directions are unit vectors in the local shading frame, in which the normal is the z axis.
+#[derive(Clone, Copy, Debug)]
+pub struct Ggx {
+    /// The width of the distribution, 0 is a perfect mirror.
+    pub alpha: f64,
+}
+
+impl Ggx {
+    pub fn new(alpha: f64) -> Ggx {
+        // Very small widths lead to numerical problems without looking any different.
+        return Ggx { alpha: alpha.max(1e-3) };
+    }
+
+    /// The density of microfacets with normal `h` per unit area of the surface.
+    pub fn d(&self, h: &Vec3) -> f64 {
+        if h.z() <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let t = h.z() * h.z() * (a2 - 1.0) + 1.0;
+        return a2 / (PI * t * t);
+    }
+
+    /// The Smith auxiliary function, the masked microfacet area relative to the visible area.
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        return ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0;
+    }
+
+    /// The fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        return 1.0 / (1.0 + self.lambda(w));
+    }
+
+    /// The fraction of microfacets visible from both `w_o` and `w_i`. The height-correlated form
+    /// accounts for high microfacets being more likely to be visible from both.
+    pub fn g2(&self, w_o: &Vec3, w_i: &Vec3) -> f64 {
+        return 1.0 / (1.0 + self.lambda(w_o) + self.lambda(w_i));
+    }
+
+    /// The density of the microfacet normals visible from `w`.
+    pub fn visible_d(&self, w: &Vec3, h: &Vec3) -> f64 {
+        return self.g1(w) * dot(w, h).max(0.0) * self.d(h) / w.z();
+    }
+
+    /// Samples a microfacet normal visible from `w` proportionally to `visible_d`, following Heitz,
+    /// "Sampling the GGX Distribution of Visible Normals" (2018).
+    pub fn sample_visible_normal(&self, w: &Vec3, u: (f64, f64)) -> Vec3 {
+        // Stretch the view so the distribution becomes a hemisphere.
+        let v = Vec3 { e: [self.alpha * w.x(), self.alpha * w.y(), w.z()] }.unit_vector();
+        let length_squared = v.x() * v.x() + v.y() * v.y();
+        let t1 = if length_squared > 0.0 {
+            Vec3 { e: [-v.y(), v.x(), 0.0] } / length_squared.sqrt()
+        } else {
+            Vec3 { e: [1.0, 0.0, 0.0] }
+        };
+        let t2 = cross(&v, &t1);
+
+        // Sample the projected area of the hemisphere as seen from `v`.
+        let r = u.0.sqrt();
+        let phi = 2.0 * PI * u.1;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + v.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let n = t1 * p1 + t2 * p2 + v * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        // Unstretch the normal.
+        return Vec3 { e: [self.alpha * n.x(), self.alpha * n.y(), n.z().max(1e-6)] }.unit_vector();
+    }
+}
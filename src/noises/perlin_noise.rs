@@ -1,4 +1,5 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 const PERMUTATION: [i32; 256] = [
     151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
@@ -46,16 +47,19 @@ fn grad(hash: i32, x: f64, y: f64, z: f64) -> f64 {
 }
 
 impl PerlinNoise {
-    pub(crate) fn new() -> PerlinNoise {
+    /// Builds the noise from a random selection of the permutation, the same seed gives the same
+    /// noise.
+    pub fn new(seed: u64) -> PerlinNoise {
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut p = [0; 512];
         for i in 0..256 {
-            let i1 = rand::thread_rng().gen_range(0..=255);
+            let i1 = rng.gen_range(0..=255);
             p[i] = PERMUTATION[i1];
             p[256 + i] = PERMUTATION[i1];
         }
         return PerlinNoise { p };
     }
-    pub(crate) fn get_value(&self, mut x: f64, mut y: f64, mut z: f64) -> f64 {
+    pub fn get_value(&self, mut x: f64, mut y: f64, mut z: f64) -> f64 {
         // See https://mrl.cs.nyu.edu/~perlin/paper445.pdf
         let ix = x.floor() as i32 & 255;
         let iy = y.floor() as i32 & 255;
@@ -1,15 +1,13 @@
-use std::sync::{Arc, Mutex};
-
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Point;
 
-use crate::vec3::Vec3;
+use crate::renderer::Framebuffer;
 use std::{thread, time};
-use crate::color::scale_color;
+use crate::color::{scale_color, ToneMapping};
 
-pub fn draw_window(rx: Arc<Mutex<Vec<Vec<Vec3>>>>, width: i32, height: i32, samples_per_pixel: usize) {
+pub fn draw_window(rx: Framebuffer, width: i32, height: i32, tone_mapping: ToneMapping) {
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
@@ -25,22 +23,18 @@ pub fn draw_window(rx: Arc<Mutex<Vec<Vec<Vec3>>>>, width: i32, height: i32, samp
     canvas.present();
     let mut event_pump = sdl_context.event_pump().unwrap();
     loop {
-        let mut x = 0;
-        let mut y = 0;
         let guard = rx.lock().unwrap();
-        let vec = guard.clone();
+        let accumulation = guard.clone();
         drop(guard);
-        for i in vec.iter() {
-            for j in i.iter() {
-                let col = scale_color(j, samples_per_pixel);
+        for y in 0..height {
+            for x in 0..width {
+                let color = match accumulation.pixel((y * width + x) as usize) {
+                    Some(color) => color,
+                    None => continue,
+                };
+                let col = scale_color(&color, 1, &tone_mapping);
                 canvas.set_draw_color(Color::RGB((col.x() * 255.0) as u8, (col.y() * 255.0) as u8, (col.z() * 255.0) as u8));
                 canvas.draw_point(Point::new(x, y));
-
-                x += 1;
-                if x == width {
-                    y += 1;
-                    x = 0;
-                }
             }
         }
 
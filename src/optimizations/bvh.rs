@@ -70,7 +70,6 @@ impl Bvh {
         let axis = get_axis(&b_box);
 
         let half = mean_split(elements, axis);
-        println!("Previous: {}, Now: {}", elements.len(), half);
 
         let left = Bvh::new_normal(&mut elements[..half as usize].to_vec());
         let right = Bvh::new_normal(&mut elements[half as usize..].to_vec());
@@ -161,7 +160,6 @@ fn gen_tree_morton(
         });
     }
     let half = binary_split(&codes, start, end);
-    println!("Previous: {}, Now: {}", end - start, half - start);
 
     let left = gen_tree_morton(sorted_hittables, codes, start, half);
     let right = gen_tree_morton(sorted_hittables, codes, half + 1, end);
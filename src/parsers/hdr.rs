@@ -0,0 +1,135 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::image::Image;
+use crate::vec3::{Color, Vec3};
+
+/// Reads a Radiance RGBE (`.hdr`) image with either flat or run-length encoded scanlines.
+pub fn read_hdr(path: &Path) -> std::io::Result<Image> {
+    let bytes = std::fs::read(path)?;
+    let mut reader = HdrReader { bytes: &bytes, position: 0 };
+
+    let magic = reader.line()?;
+    if !magic.starts_with("#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+    loop {
+        let line = reader.line()?;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid("only the 32-bit_rle_rgbe format is supported"));
+        }
+    }
+    let resolution = reader.line()?;
+    let parts: Vec<&str> = resolution.split_whitespace().collect();
+    if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+        return Err(invalid("only images stored from top to bottom and left to right are supported"));
+    }
+    let height: i32 = parts[1].parse().map_err(|_| invalid("invalid image height"))?;
+    let width: i32 = parts[3].parse().map_err(|_| invalid("invalid image width"))?;
+    if width <= 0 || height <= 0 || width.checked_mul(height).is_none() {
+        return Err(invalid("invalid image size"));
+    }
+
+    let mut image = Image::new(width, height);
+    let mut scanline = vec![[0u8; 4]; width as usize];
+    for y in 0..height {
+        reader.scanline(&mut scanline)?;
+        for x in 0..width {
+            image.set(x, y, rgbe_to_color(scanline[x as usize]));
+        }
+    }
+    return Ok(image);
+}
+
+struct HdrReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HdrReader<'a> {
+    fn byte(&mut self) -> std::io::Result<u8> {
+        let byte = *self.bytes.get(self.position).ok_or_else(|| invalid("unexpected end of file"))?;
+        self.position += 1;
+        return Ok(byte);
+    }
+
+    fn line(&mut self) -> std::io::Result<String> {
+        let mut line = Vec::new();
+        loop {
+            match self.byte()? {
+                b'\n' => break,
+                byte => line.push(byte),
+            }
+        }
+        return Ok(String::from_utf8_lossy(&line).trim().to_string());
+    }
+
+    fn scanline(&mut self, scanline: &mut Vec<[u8; 4]>) -> std::io::Result<()> {
+        let width = scanline.len();
+        let rest = &self.bytes[self.position..];
+        let run_length_encoded = width >= 8 && width < 0x8000 && rest.len() >= 4
+            && rest[0] == 2 && rest[1] == 2 && rest[2] & 0x80 == 0;
+        if !run_length_encoded {
+            for pixel in scanline.iter_mut() {
+                for channel in 0..4 {
+                    pixel[channel] = self.byte()?;
+                }
+            }
+            return Ok(());
+        }
+
+        self.position += 2;
+        let encoded_width = ((self.byte()? as usize) << 8) | self.byte()? as usize;
+        if encoded_width != width {
+            return Err(invalid("scanline width does not match the image width"));
+        }
+        // Every channel is stored separately as a sequence of runs and literal spans.
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.byte()? as usize;
+                if count > 128 {
+                    let value = self.byte()?;
+                    let count = count - 128;
+                    if x + count > width {
+                        return Err(invalid("run exceeds the scanline"));
+                    }
+                    for pixel in scanline[x..x + count].iter_mut() {
+                        pixel[channel] = value;
+                    }
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid("invalid literal span"));
+                    }
+                    for pixel in scanline[x..x + count].iter_mut() {
+                        pixel[channel] = self.byte()?;
+                    }
+                    x += count;
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+fn rgbe_to_color(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Vec3::new();
+    }
+    let scale = 2f64.powi(rgbe[3] as i32 - 136);
+    return Color {
+        e: [
+            (rgbe[0] as f64 + 0.5) * scale,
+            (rgbe[1] as f64 + 0.5) * scale,
+            (rgbe[2] as f64 + 0.5) * scale,
+        ],
+    };
+}
+
+fn invalid(message: &str) -> Error {
+    return Error::new(ErrorKind::InvalidData, message.to_string());
+}
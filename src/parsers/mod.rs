@@ -1,2 +1,4 @@
 pub mod from_stl;
+pub mod hdr;
 pub mod obj;
+pub mod scene;
@@ -0,0 +1,759 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::camera::{Camera, create_camera, Projection};
+use crate::color::{ToneMapOperator, ToneMapping};
+use crate::filter::{Filter, FilterKind};
+use crate::hittables::hittable::Hittable;
+use crate::hittables::moving::{Keyframe, Moving};
+use crate::hittables::sphere::Sphere;
+use crate::hittables::triangle::Triangle;
+use crate::lights::environment::{Background, EnvironmentMap};
+use crate::lights::light::Light;
+use crate::lights::sky::Sky;
+use crate::material::Material;
+use crate::noises::perlin_noise::PerlinNoise;
+use crate::optimizations::bvh::Bvh;
+use crate::parsers::from_stl::read_stl;
+use crate::parsers::hdr::read_hdr;
+use crate::parsers::obj::read_obj;
+use crate::samplers::sampler::SamplingMethod;
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::textures::texture::Texture;
+use crate::vec3::{cross, Vec3};
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    camera: CameraDescription,
+    #[serde(default)]
+    render: RenderDescription,
+    #[serde(default)]
+    textures: HashMap<String, TextureDescription>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    spheres: Vec<SphereDescription>,
+    #[serde(default)]
+    triangles: Vec<TriangleDescription>,
+    #[serde(default)]
+    meshes: Vec<MeshDescription>,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    background: Option<BackgroundDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    look_from: [f64; 3],
+    look_at: [f64; 3],
+    #[serde(default = "default_vup")]
+    vup: [f64; 3],
+    vfov: f64,
+    /// Defaults to the distance between `look_from` and `look_at`.
+    focus_dist: Option<f64>,
+    /// The radius of the lens, zero for a pinhole camera.
+    #[serde(default)]
+    aperture: f64,
+    /// The pixel, counted from the top left corner, whose distance becomes the focus distance.
+    autofocus: Option<Spanned<[f64; 2]>>,
+    /// The times at which the shutter opens and closes.
+    #[serde(default)]
+    shutter: [f64; 2],
+    #[serde(default)]
+    projection: ProjectionDescription,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum ProjectionDescription {
+    #[default]
+    Perspective,
+    Orthographic {
+        height: f64,
+    },
+    Equirectangular,
+    Fisheye {
+        fov: f64,
+    },
+}
+
+fn default_vup() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RenderDescription {
+    width: Spanned<i32>,
+    height: Spanned<i32>,
+    samples_per_pixel: Spanned<usize>,
+    samples_per_pass: usize,
+    noise_threshold: Option<Spanned<f64>>,
+    min_samples_per_pixel: usize,
+    sampler: SamplingMethod,
+    seed: u64,
+    filter: FilterKind,
+    /// In pixels, each filter has its own default.
+    filter_radius: Option<Spanned<f64>>,
+    tile_size: Spanned<i32>,
+    depth: Spanned<i32>,
+    russian_roulette: bool,
+    min_bounces: Spanned<i32>,
+    light_sampling: bool,
+    tone_mapping: ToneMapOperator,
+    /// In stops.
+    exposure: f64,
+    denoise: bool,
+}
+
+impl Default for RenderDescription {
+    fn default() -> Self {
+        let settings = RenderSettings::default();
+        RenderDescription {
+            width: unlocated(settings.width),
+            height: unlocated(settings.height),
+            samples_per_pixel: unlocated(settings.samples_per_pixel),
+            samples_per_pass: settings.samples_per_pass,
+            noise_threshold: settings.noise_threshold.map(unlocated),
+            min_samples_per_pixel: settings.min_samples_per_pixel,
+            sampler: settings.sampler,
+            seed: settings.seed,
+            filter: settings.filter.kind(),
+            filter_radius: None,
+            tile_size: unlocated(settings.tile_size),
+            depth: unlocated(settings.depth),
+            russian_roulette: settings.russian_roulette,
+            min_bounces: unlocated(settings.min_bounces),
+            light_sampling: settings.light_sampling,
+            tone_mapping: settings.tone_mapping.operator,
+            exposure: settings.tone_mapping.exposure,
+            denoise: settings.denoise,
+        }
+    }
+}
+
+/// A default value, which has no place in the scene file.
+fn unlocated<T>(value: T) -> Spanned<T> {
+    return Spanned::new(0..0, value);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    Checker {
+        color1: [f64; 3],
+        color2: [f64; 3],
+        size: f64,
+    },
+    Perlin {
+        scale: f64,
+        color1: [f64; 3],
+        color2: [f64; 3],
+        /// Chooses another noise, textures with the same name and seed look the same.
+        #[serde(default)]
+        seed: u64,
+    },
+    Image {
+        path: Spanned<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDescription {
+    Dielectric {
+        ir: f64,
+        tint: Spanned<String>,
+        #[serde(default)]
+        emission: [f64; 3],
+    },
+    Metal {
+        albedo: Spanned<String>,
+        fuzz: f64,
+        #[serde(default)]
+        emission: [f64; 3],
+    },
+    Diffuse {
+        albedo: Spanned<String>,
+        #[serde(default)]
+        emission: [f64; 3],
+    },
+    CookTorrance {
+        diffuse: Spanned<String>,
+        k_d: Spanned<f64>,
+        specular: Spanned<String>,
+        roughness: f64,
+        #[serde(default)]
+        emission: [f64; 3],
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDescription {
+    position: [f64; 3],
+    radius: f64,
+    material: Spanned<String>,
+    #[serde(default)]
+    motion: Vec<Spanned<KeyframeDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriangleDescription {
+    a: [f64; 3],
+    b: [f64; 3],
+    c: [f64; 3],
+    material: Spanned<String>,
+    #[serde(default)]
+    motion: Vec<Spanned<KeyframeDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDescription {
+    path: Spanned<String>,
+    material: Spanned<String>,
+    #[serde(default)]
+    motion: Vec<Spanned<KeyframeDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDescription {
+    time: f64,
+    #[serde(default)]
+    translation: [f64; 3],
+    /// The rotations around the x, y and z axes in degrees.
+    #[serde(default)]
+    rotation: [f64; 3],
+    #[serde(default = "default_scale")]
+    scale: [f64; 3],
+}
+
+fn default_scale() -> [f64; 3] {
+    return [1.0, 1.0, 1.0];
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum LightDescription {
+    Point {
+        position: [f64; 3],
+        intensity: [f64; 3],
+    },
+    Spot {
+        position: [f64; 3],
+        direction: [f64; 3],
+        intensity: [f64; 3],
+        /// The half angle of the cone in degrees.
+        cone_angle: f64,
+        /// The angle in degrees over which the light fades out towards the edge of the cone.
+        #[serde(default)]
+        falloff: f64,
+    },
+    Directional {
+        direction: [f64; 3],
+        irradiance: [f64; 3],
+    },
+    Rect {
+        corner: [f64; 3],
+        edge_u: Spanned<[f64; 3]>,
+        edge_v: Spanned<[f64; 3]>,
+        emission: [f64; 3],
+    },
+    Disc {
+        center: [f64; 3],
+        normal: Spanned<[f64; 3]>,
+        radius: Spanned<f64>,
+        emission: [f64; 3],
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDescription {
+    Solid {
+        color: [f64; 3],
+    },
+    Gradient {
+        #[serde(default = "default_gradient_bottom")]
+        bottom: [f64; 3],
+        #[serde(default = "default_gradient_top")]
+        top: [f64; 3],
+    },
+    Image {
+        /// An equirectangular Radiance HDR image.
+        path: Spanned<String>,
+        /// The rotation around the up axis in degrees.
+        #[serde(default)]
+        rotation: f64,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    Sky {
+        /// The direction towards the sun, which must point above the horizon.
+        sun_direction: Spanned<[f64; 3]>,
+        #[serde(default = "default_turbidity")]
+        turbidity: f64,
+        #[serde(default = "default_ground_albedo")]
+        ground_albedo: [f64; 3],
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+        /// The angular diameter of the sun in degrees.
+        #[serde(default = "default_sun_size")]
+        sun_size: f64,
+    },
+}
+
+fn default_gradient_bottom() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_gradient_top() -> [f64; 3] {
+    [0.5, 0.7, 1.0]
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+fn default_turbidity() -> f64 {
+    3.0
+}
+
+fn default_ground_albedo() -> [f64; 3] {
+    [0.3, 0.3, 0.3]
+}
+
+fn default_sun_size() -> f64 {
+    0.53
+}
+
+/// Loads a TOML scene description and builds the camera, the render settings and the list of
+/// hittables that is handed to the BVH. Relative paths are resolved against the scene file.
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+    let source = std::fs::read_to_string(path).map_err(|e| SceneError {
+        path: path.to_path_buf(),
+        line: None,
+        message: format!("cannot read the scene file: {}", e),
+    })?;
+    let description: SceneDescription = toml::from_str(&source).map_err(|e| SceneError {
+        path: path.to_path_buf(),
+        line: e.span().map(|span| line_of(&source, span.start)),
+        message: e.message().to_string(),
+    })?;
+
+    let builder = SceneBuilder {
+        path,
+        source: &source,
+        directory: path.parent().unwrap_or(Path::new(".")),
+        textures: &description.textures,
+    };
+    builder.build(&description)
+}
+
+struct SceneBuilder<'a> {
+    path: &'a Path,
+    source: &'a str,
+    directory: &'a Path,
+    textures: &'a HashMap<String, TextureDescription>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn build(&self, description: &SceneDescription) -> Result<Scene, SceneError> {
+        let render = &description.render;
+        for size in [&render.width, &render.height] {
+            if *size.get_ref() <= 0 {
+                return Err(self.error(Some(size.span().start), String::from("the image size must be positive")));
+            }
+        }
+        if *render.samples_per_pixel.get_ref() == 0 {
+            return Err(self.error(
+                Some(render.samples_per_pixel.span().start),
+                String::from("the number of samples per pixel must be positive"),
+            ));
+        }
+        if *render.tile_size.get_ref() <= 0 {
+            return Err(self.error(Some(render.tile_size.span().start), String::from("the tile size must be positive")));
+        }
+        if let Some(radius) = render.filter_radius.as_ref().filter(|radius| !positive(*radius.get_ref())) {
+            return Err(self.error(Some(radius.span().start), String::from("the filter radius must be positive")));
+        }
+        if let Some(threshold) = render.noise_threshold.as_ref().filter(|threshold| !positive(*threshold.get_ref())) {
+            return Err(self.error(Some(threshold.span().start), String::from("the noise threshold must be positive")));
+        }
+        if *render.depth.get_ref() <= 0 {
+            return Err(self.error(Some(render.depth.span().start), String::from("the depth must be positive")));
+        }
+        if *render.min_bounces.get_ref() < 0 {
+            return Err(self.error(
+                Some(render.min_bounces.span().start),
+                String::from("the minimum number of bounces must not be negative"),
+            ));
+        }
+        let settings = RenderSettings {
+            width: *render.width.get_ref(),
+            height: *render.height.get_ref(),
+            samples_per_pixel: *render.samples_per_pixel.get_ref(),
+            samples_per_pass: render.samples_per_pass,
+            noise_threshold: render.noise_threshold.as_ref().map(|threshold| *threshold.get_ref()),
+            min_samples_per_pixel: render.min_samples_per_pixel,
+            sampler: render.sampler,
+            seed: render.seed,
+            filter: {
+                let filter = Filter::with_default_radius(render.filter);
+                render.filter_radius.as_ref().map_or(filter, |radius| filter.with_radius(*radius.get_ref()))
+            },
+            tile_size: *render.tile_size.get_ref(),
+            depth: *render.depth.get_ref(),
+            russian_roulette: render.russian_roulette,
+            min_bounces: *render.min_bounces.get_ref(),
+            light_sampling: render.light_sampling,
+            tone_mapping: ToneMapping {
+                operator: render.tone_mapping,
+                exposure: render.exposure,
+            },
+            denoise: render.denoise,
+            ..RenderSettings::default()
+        };
+
+        let mut materials = HashMap::new();
+        for (name, material) in description.materials.iter() {
+            materials.insert(name.clone(), Arc::new(self.build_material(material)?));
+        }
+
+        let mut hittables: Vec<Arc<Hittable>> = Vec::new();
+        for sphere in description.spheres.iter() {
+            let hittable = Arc::new(Hittable::Sphere {
+                sphere: Sphere {
+                    position: to_vec3(sphere.position),
+                    radius: sphere.radius,
+                    material: self.material(&materials, &sphere.material)?,
+                },
+            });
+            hittables.push(self.with_motion(hittable, &sphere.motion)?);
+        }
+        for triangle in description.triangles.iter() {
+            let hittable = Arc::new(Hittable::Triangle {
+                triangle: Triangle::new(
+                    to_vec3(triangle.a),
+                    to_vec3(triangle.b),
+                    to_vec3(triangle.c),
+                    self.material(&materials, &triangle.material)?,
+                ),
+            });
+            hittables.push(self.with_motion(hittable, &triangle.motion)?);
+        }
+        for mesh in description.meshes.iter() {
+            let material = self.material(&materials, &mesh.material)?;
+            let mesh_path = self.resolve(&mesh.path)?;
+            let mut triangles = match mesh_path.extension().and_then(|e| e.to_str()) {
+                Some("obj") => read_obj(&mesh_path, material),
+                Some("stl") => read_stl(mesh_path.to_string_lossy().into_owned(), material),
+                _ => {
+                    return Err(self.error(
+                        Some(mesh.path.span().start),
+                        format!("unsupported mesh format `{}`, expected .obj or .stl", mesh.path.get_ref()),
+                    ));
+                }
+            };
+            if mesh.motion.is_empty() {
+                hittables.extend(triangles);
+            } else {
+                // The mesh moves as a whole, around the center of all its triangles.
+                hittables.push(self.with_motion(Bvh::new_morton(&mut triangles), &mesh.motion)?);
+            }
+        }
+        if hittables.is_empty() {
+            return Err(self.error(None, String::from("the scene does not contain any objects")));
+        }
+
+        let camera = self.build_camera(&description.camera, &settings, &hittables)?;
+
+        let mut lights = Vec::new();
+        for light in description.lights.iter() {
+            lights.push(self.build_light(light)?);
+        }
+        let background = match &description.background {
+            Some(background) => self.build_background(background)?,
+            None => Background::default(),
+        };
+
+        return Ok(Scene {
+            camera,
+            settings,
+            hittables,
+            lights,
+            background,
+        });
+    }
+
+    fn build_light(&self, light: &LightDescription) -> Result<Light, SceneError> {
+        return Ok(match light {
+            LightDescription::Point { position, intensity } => Light::Point {
+                position: to_vec3(*position),
+                intensity: to_vec3(*intensity),
+            },
+            LightDescription::Spot { position, direction, intensity, cone_angle, falloff } => Light::spot(
+                to_vec3(*position),
+                to_vec3(*direction),
+                to_vec3(*intensity),
+                *cone_angle,
+                *falloff,
+            ),
+            LightDescription::Directional { direction, irradiance } => Light::Directional {
+                direction: to_vec3(*direction).unit_vector(),
+                irradiance: to_vec3(*irradiance),
+            },
+            LightDescription::Rect { corner, edge_u, edge_v, emission } => {
+                let (u, v) = (to_vec3(*edge_u.get_ref()), to_vec3(*edge_v.get_ref()));
+                if cross(&u, &v).near_zero() {
+                    return Err(self.error(Some(edge_u.span().start), String::from("the rect light has no area")));
+                }
+                Light::Rect { corner: to_vec3(*corner), edge_u: u, edge_v: v, emission: to_vec3(*emission) }
+            }
+            LightDescription::Disc { center, normal, radius, emission } => {
+                if !positive(*radius.get_ref()) {
+                    return Err(self.error(Some(radius.span().start), String::from("the radius must be positive")));
+                }
+                if to_vec3(*normal.get_ref()).near_zero() {
+                    return Err(self.error(Some(normal.span().start), String::from("the normal must not be zero")));
+                }
+                Light::Disc {
+                    center: to_vec3(*center),
+                    normal: to_vec3(*normal.get_ref()).unit_vector(),
+                    radius: *radius.get_ref(),
+                    emission: to_vec3(*emission),
+                }
+            }
+        });
+    }
+
+    fn build_background(&self, background: &BackgroundDescription) -> Result<Background, SceneError> {
+        return Ok(match background {
+            BackgroundDescription::Solid { color } => Background::Solid { color: to_vec3(*color) },
+            BackgroundDescription::Gradient { bottom, top } => Background::Gradient {
+                bottom: to_vec3(*bottom),
+                top: to_vec3(*top),
+            },
+            BackgroundDescription::Image { path, rotation, intensity } => {
+                let image = read_hdr(&self.resolve(path)?).map_err(|e| {
+                    self.error(Some(path.span().start), format!("cannot read `{}`: {}", path.get_ref(), e))
+                })?;
+                Background::Map { map: Arc::new(EnvironmentMap::new(image, *rotation, *intensity)) }
+            }
+            BackgroundDescription::Sky { sun_direction, turbidity, ground_albedo, intensity, sun_size } => {
+                let direction = to_vec3(*sun_direction.get_ref()).unit_vector();
+                if !(direction.y() > 0.0) {
+                    return Err(self.error(
+                        Some(sun_direction.span().start),
+                        String::from("the sun must be above the horizon"),
+                    ));
+                }
+                Background::Sky {
+                    sky: Arc::new(Sky::new(direction, *turbidity, to_vec3(*ground_albedo), *intensity, *sun_size)),
+                }
+            }
+        });
+    }
+
+    fn build_camera(
+        &self,
+        camera: &CameraDescription,
+        settings: &RenderSettings,
+        hittables: &Vec<Arc<Hittable>>,
+    ) -> Result<Camera, SceneError> {
+        let look_from = to_vec3(camera.look_from);
+        let look_at = to_vec3(camera.look_at);
+        let result = create_camera(
+            look_from,
+            look_at,
+            to_vec3(camera.vup),
+            camera.vfov,
+            settings.aspect_ratio(),
+            camera.focus_dist.unwrap_or_else(|| (look_at - look_from).length()),
+            camera.aperture,
+        )
+        .with_shutter(camera.shutter[0], camera.shutter[1])
+        .with_projection(match camera.projection {
+            ProjectionDescription::Perspective => Projection::Perspective,
+            ProjectionDescription::Orthographic { height } => Projection::Orthographic { height },
+            ProjectionDescription::Equirectangular => Projection::Equirectangular,
+            ProjectionDescription::Fisheye { fov } => Projection::Fisheye { fov },
+        });
+        return match &camera.autofocus {
+            Some(pixel) => {
+                let [x, y] = *pixel.get_ref();
+                // The same mapping from pixels to the image plane as the renderer uses.
+                let s = (x + 0.5) / (settings.width as f64 - 1.0);
+                let t = (settings.height as f64 - y - 0.5) / (settings.height as f64 - 1.0);
+                result.autofocus(hittables, s, t).ok_or_else(|| {
+                    self.error(Some(pixel.span().start), String::from("there is nothing to focus on at this pixel"))
+                })
+            }
+            None => Ok(result),
+        };
+    }
+
+    fn build_material(&self, material: &MaterialDescription) -> Result<Material, SceneError> {
+        return Ok(match material {
+            MaterialDescription::Dielectric { ir, tint, emission } => Material::Dielectric {
+                ir: *ir,
+                tint: self.texture(tint)?,
+                emission: to_vec3(*emission),
+            },
+            MaterialDescription::Metal { albedo, fuzz, emission } => Material::Metal {
+                albedo: self.texture(albedo)?,
+                fuzz: *fuzz,
+                emission: to_vec3(*emission),
+            },
+            MaterialDescription::Diffuse { albedo, emission } => Material::Diffuse {
+                albedo: self.texture(albedo)?,
+                emission: to_vec3(*emission),
+            },
+            MaterialDescription::CookTorrance { diffuse, k_d, specular, roughness, emission } => {
+                if !(0.0..=1.0).contains(k_d.get_ref()) {
+                    return Err(self.error(
+                        Some(k_d.span().start),
+                        format!("k_d must be between 0 and 1, not {}", k_d.get_ref()),
+                    ));
+                }
+                Material::CookTorrance {
+                    diffuse: self.texture(diffuse)?,
+                    k_d: *k_d.get_ref(),
+                    specular: self.texture(specular)?,
+                    roughness: *roughness,
+                    emission: to_vec3(*emission),
+                }
+            }
+        });
+    }
+
+    fn texture(&self, name: &Spanned<String>) -> Result<Texture, SceneError> {
+        let description = self.textures.get(name.get_ref()).ok_or_else(|| {
+            self.error(Some(name.span().start), format!("unknown texture `{}`", name.get_ref()))
+        })?;
+        return Ok(match description {
+            TextureDescription::Solid { color } => Texture::Solid { color: to_vec3(*color) },
+            TextureDescription::Checker { color1, color2, size } => Texture::Checker {
+                color1: to_vec3(*color1),
+                color2: to_vec3(*color2),
+                size: *size,
+            },
+            TextureDescription::Perlin { scale, color1, color2, seed } => Texture::Perlin {
+                perlin_noise: PerlinNoise::new(name_seed(name.get_ref()) ^ *seed),
+                scale: *scale,
+                color1: to_vec3(*color1),
+                color2: to_vec3(*color2),
+            },
+            TextureDescription::Image { path } => {
+                Texture::parse_mapped(self.resolve(path)?.to_string_lossy().into_owned())
+            }
+        });
+    }
+
+    fn material(
+        &self,
+        materials: &HashMap<String, Arc<Material>>,
+        name: &Spanned<String>,
+    ) -> Result<Arc<Material>, SceneError> {
+        return materials.get(name.get_ref()).cloned().ok_or_else(|| {
+            self.error(Some(name.span().start), format!("unknown material `{}`", name.get_ref()))
+        });
+    }
+
+    fn resolve(&self, path: &Spanned<String>) -> Result<PathBuf, SceneError> {
+        let resolved = self.directory.join(path.get_ref());
+        if !resolved.is_file() {
+            return Err(self.error(
+                Some(path.span().start),
+                format!("cannot find the file `{}`", resolved.display()),
+            ));
+        }
+        return Ok(resolved);
+    }
+
+    /// Wraps the hittable so it follows the keyframes, if there are any.
+    fn with_motion(
+        &self,
+        hittable: Arc<Hittable>,
+        motion: &Vec<Spanned<KeyframeDescription>>,
+    ) -> Result<Arc<Hittable>, SceneError> {
+        if motion.is_empty() {
+            return Ok(hittable);
+        }
+        let mut keyframes = Vec::new();
+        for keyframe in motion.iter() {
+            if !keyframe.get_ref().scale.iter().all(|scale| positive(*scale)) {
+                return Err(self.error(Some(keyframe.span().start), String::from("the scale must be positive")));
+            }
+            let description = keyframe.get_ref();
+            keyframes.push(Keyframe {
+                time: description.time,
+                translation: to_vec3(description.translation),
+                rotation: to_vec3(description.rotation),
+                scale: to_vec3(description.scale),
+            });
+        }
+        return Ok(Arc::new(Hittable::Moving { moving: Moving::new(hittable, keyframes) }));
+    }
+
+    fn error(&self, offset: Option<usize>, message: String) -> SceneError {
+        SceneError {
+            path: self.path.to_path_buf(),
+            line: offset.map(|offset| line_of(self.source, offset)),
+            message,
+        }
+    }
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    return source[..offset.min(source.len())].matches('\n').count() + 1;
+}
+
+fn to_vec3(e: [f64; 3]) -> Vec3 {
+    return Vec3 { e };
+}
+
+/// Whether the value is a finite number above zero, which `nan` and `inf` in TOML are not.
+fn positive(value: f64) -> bool {
+    return value.is_finite() && value > 0.0;
+}
+
+/// A seed derived from a name that stays the same between runs and builds (FNV-1a).
+fn name_seed(name: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    return hash;
+}
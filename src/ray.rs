@@ -2,17 +2,19 @@ use crate::vec3::{Point3, Vec3};
 
 #[derive(Clone, Copy)]
 pub struct Ray {
-    pub(crate) origin: Point3,
-    pub(crate) direction: Vec3,
-    pub(crate) inv_direction: Vec3,
-    pub(crate) sign: [usize; 3],
+    pub origin: Point3,
+    pub direction: Vec3,
+    /// The point in time within the shutter interval of the camera the ray travels at.
+    pub time: f64,
+    pub inv_direction: Vec3,
+    pub sign: [usize; 3],
 }
 
 impl Ray {
-    pub(crate) fn at(&self, t: f64) -> Point3 {
+    pub fn at(&self, t: f64) -> Point3 {
         return self.origin + self.direction * t;
     }
-    pub(crate) fn new(origin: Point3, direction: Vec3) -> Ray {
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Ray {
         let inv_direction = Vec3 {
             e: [
                 1.0 / direction.x(),
@@ -28,6 +30,7 @@ impl Ray {
         return Ray {
             origin,
             direction,
+            time,
             inv_direction,
             sign,
         };
@@ -0,0 +1,388 @@
+use std::f64::INFINITY;
+use std::sync::{Arc, Mutex};
+
+use threadpool::ThreadPool;
+
+use crate::aov::{Aov, AovIds};
+use crate::denoiser::Denoiser;
+use crate::filter::Filter;
+use crate::hittables::hittable::Hittable;
+use crate::image::Image;
+use crate::integrator::Integrator;
+use crate::lights::environment::luminance;
+use crate::optimizations::bvh::Bvh;
+use crate::samplers::sampler::Sampler;
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::tiles::{spiral_tiles, Tile};
+use crate::vec3::{Color, Vec3};
+
+/// The samples taken so far for every pixel, stored row by row from the top. Passes add samples
+/// until every pixel has `samples_per_pixel` of them or, with adaptive sampling, until its noise
+/// falls below the threshold.
+#[derive(Clone)]
+pub struct Accumulation {
+    pub width: i32,
+    pub height: i32,
+    /// The samples that reach the pixel through the reconstruction filter, multiplied by their
+    /// filter weights, and the sums of these weights.
+    pub sums: Vec<Color>,
+    pub weights: Vec<f64>,
+    /// The sums of the luminances and squared luminances of the samples taken within the pixel,
+    /// for estimating the noise.
+    pub luminances: Vec<f64>,
+    pub squares: Vec<f64>,
+    pub samples: Vec<u32>,
+}
+
+impl Accumulation {
+    pub fn new(width: i32, height: i32) -> Accumulation {
+        let size = (width * height) as usize;
+        return Accumulation {
+            width,
+            height,
+            sums: vec![Vec3::new(); size],
+            weights: vec![0.0; size],
+            luminances: vec![0.0; size],
+            squares: vec![0.0; size],
+            samples: vec![0; size],
+        };
+    }
+
+    /// Counts samples taken within a pixel, with the sums of their luminances and squares.
+    pub fn add_samples(&mut self, index: usize, luminances: f64, squares: f64, samples: u32) {
+        self.luminances[index] += luminances;
+        self.squares[index] += squares;
+        self.samples[index] += samples;
+    }
+
+    /// Adds samples weighted by the reconstruction filter to a pixel.
+    pub fn add_weighted(&mut self, index: usize, sum: Color, weight: f64) {
+        self.sums[index] = self.sums[index] + sum;
+        self.weights[index] += weight;
+    }
+
+    /// The filtered value of a pixel, if any sample has reached it yet.
+    pub fn pixel(&self, index: usize) -> Option<Color> {
+        if self.weights[index] <= 0.0 {
+            return None;
+        }
+        return Some(self.sums[index] / self.weights[index]);
+    }
+
+    /// The smallest number of samples of any pixel.
+    pub fn min_samples(&self) -> u32 {
+        return self.samples.iter().copied().min().unwrap_or(0);
+    }
+
+    /// The standard error of the mean luminance of a pixel relative to that luminance. Luminances
+    /// below 0.05 count as 0.05, so dark pixels are not sampled forever.
+    pub fn relative_error(&self, index: usize) -> f64 {
+        let n = self.samples[index] as f64;
+        if n < 2.0 {
+            return INFINITY;
+        }
+        let mean = self.luminances[index] / n;
+        let variance = ((self.squares[index] / n - mean * mean) * n / (n - 1.0)).max(0.0);
+        return (variance / n).sqrt() / mean.max(0.05);
+    }
+
+    /// The number of samples the next pass adds to a pixel, zero once it is finished.
+    pub fn samples_needed(&self, index: usize, settings: &RenderSettings) -> u32 {
+        let done = self.samples[index];
+        if let Some(threshold) = settings.noise_threshold {
+            if done >= settings.min_samples_per_pixel as u32 && self.relative_error(index) < threshold {
+                return 0;
+            }
+        }
+        return (settings.samples_per_pixel as u32).saturating_sub(done).min(settings.samples_per_pass.max(1) as u32);
+    }
+
+    /// The fewest samples among the pixels that still need samples and the number of these pixels.
+    pub fn progress(&self, settings: &RenderSettings) -> (u32, usize) {
+        let remaining: Vec<usize> = (0..self.samples.len()).filter(|i| self.samples_needed(*i, settings) > 0).collect();
+        let done = remaining.iter().map(|i| self.samples[*i]).min().unwrap_or(0);
+        return (done, remaining.len());
+    }
+
+    /// Filters the samples of every pixel into an image.
+    pub fn resolve(&self) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            if let Some(color) = self.pixel(i) {
+                *pixel = color;
+            }
+        }
+        return image;
+    }
+
+    /// Shows the number of samples of every pixel, from blue for none over green to red for
+    /// `max_samples`.
+    pub fn heatmap(&self, max_samples: usize) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for (i, samples) in self.samples.iter().enumerate() {
+            let t = (*samples as f64 / max_samples.max(1) as f64).min(1.0);
+            image.pixels[i] = if t < 0.5 {
+                Color { e: [0.0, 2.0 * t, 1.0 - 2.0 * t] }
+            } else {
+                Color { e: [2.0 * t - 1.0, 2.0 - 2.0 * t, 0.0] }
+            };
+        }
+        return image;
+    }
+}
+
+/// An accumulation that can be observed while the render is running, e.g. by a preview window.
+pub type Framebuffer = Arc<Mutex<Accumulation>>;
+
+/// Renders the scene with the given settings. The camera is adjusted to the aspect ratio of the
+/// image size in the settings.
+pub fn render(scene: &Scene, settings: &RenderSettings) -> Image {
+    let world = build_world(scene);
+    let framebuffer = new_framebuffer(settings.width, settings.height);
+    render_to_framebuffer(scene, &world, settings, &framebuffer);
+    let image = resolve_framebuffer(&framebuffer);
+    if settings.denoise {
+        return denoise(scene, &world, settings, &image);
+    }
+    return image;
+}
+
+/// Builds the bounding volume hierarchy over the objects of the scene, which all parts of a render
+/// share instead of building their own.
+pub fn build_world(scene: &Scene) -> Arc<Hittable> {
+    return Bvh::new_morton(&mut scene.hittables.clone());
+}
+
+/// Renders the albedo, normal and depth of the scene and uses them to denoise an image of it.
+pub fn denoise(scene: &Scene, world: &Arc<Hittable>, settings: &RenderSettings, image: &Image) -> Image {
+    let features = render_aovs(scene, world, settings, &[Aov::Albedo, Aov::Normal, Aov::Depth]);
+    return Denoiser::default().denoise(image, &features[0], &features[1], &features[2]);
+}
+
+pub fn new_framebuffer(width: i32, height: i32) -> Framebuffer {
+    return Arc::new(Mutex::new(Accumulation::new(width, height)));
+}
+
+/// Renders the scene into a framebuffer that can be observed while the render is running, for
+/// example by a preview window.
+pub fn render_to_framebuffer(scene: &Scene, world: &Arc<Hittable>, settings: &RenderSettings, framebuffer: &Framebuffer) {
+    render_passes(scene, world, settings, framebuffer, &mut |_| {});
+}
+
+/// Renders the scene in passes over the whole image that add up to `samples_per_pass` samples to
+/// every pixel, until all pixels have `samples_per_pixel` samples or are below the noise threshold.
+/// Samples already in the framebuffer are kept, so a render can be continued. `after_pass` is
+/// called after every pass.
+pub fn render_passes(
+    scene: &Scene,
+    world: &Arc<Hittable>,
+    settings: &RenderSettings,
+    framebuffer: &Framebuffer,
+    after_pass: &mut dyn FnMut(&Framebuffer),
+) {
+    let settings = *settings;
+    let cam = scene.camera.with_aspect_ratio(settings.aspect_ratio());
+    let integrator = Arc::new(Integrator::new(scene, world, &settings));
+    let pool = ThreadPool::new(settings.threads);
+    let tiles = spiral_tiles(settings.width, settings.height, settings.tile_size);
+    // How far beyond its pixel a sample reaches through the filter.
+    let margin = (settings.filter.radius() - 0.5).ceil().max(0.0) as i32;
+
+    loop {
+        let (_, remaining) = framebuffer.lock().unwrap().progress(&settings);
+        if remaining == 0 {
+            break;
+        }
+        // The samples that the filter spreads into the pixels of other tiles. They are added after
+        // the pass in the order of the tiles, so the image does not depend on which tile finishes
+        // first.
+        let spills = Arc::new(Mutex::new(vec![Vec::new(); tiles.len()]));
+        for (tile_index, tile) in tiles.iter().copied().enumerate() {
+            let integrator = integrator.clone();
+            let result = framebuffer.clone();
+            let spills = spills.clone();
+            pool.execute(move || {
+                let mut counts = Vec::with_capacity((tile.width * tile.height) as usize);
+                {
+                    let result = result.lock().unwrap();
+                    for row in 0..tile.height {
+                        let offset = tile.row_offset(row, settings.width);
+                        counts.extend((offset..offset + tile.width as usize).map(|i| (result.samples[i], result.samples_needed(i, &settings))));
+                    }
+                }
+                let mut sampler = Sampler::new(settings.sampler, settings.samples_per_pixel as u32, settings.seed);
+                // Render into a buffer of the tile and lock the framebuffer only to add it.
+                let mut splats = Splats::new(&tile, margin, settings.width, settings.height);
+                let mut statistics = Vec::with_capacity(counts.len());
+                for row in 0..tile.height {
+                    let x = settings.height - (tile.y + row) - 1;
+                    for column in 0..tile.width {
+                        let j = tile.x + column;
+                        let (done, count) = counts[(row * tile.width + column) as usize];
+                        let mut luminances = 0.0;
+                        let mut squares = 0.0;
+                        for index in done..done + count {
+                            sampler.start_sample(j, x, index);
+                            let (offset_u, offset_v) = sampler.get_2d();
+                            let u = (j as f64 + offset_u) / (settings.width as f64 - 1.0);
+                            let v = (x as f64 + offset_v) / (settings.height as f64 - 1.0);
+                            let r = cam.get_ray(u, v, &mut sampler);
+
+                            let color = integrator.color_at(&r, &mut sampler);
+                            splats.add(&settings.filter, j as f64 + offset_u, (tile.y + row) as f64 + 1.0 - offset_v, color);
+                            luminances += luminance(&color);
+                            squares += luminance(&color).powi(2);
+                        }
+                        statistics.push((luminances, squares, count));
+                    }
+                }
+                let mut spill = Vec::new();
+                let mut result = result.lock().unwrap();
+                for row in 0..tile.height {
+                    let offset = tile.row_offset(row, settings.width);
+                    let start = (row * tile.width) as usize;
+                    for (i, (luminances, squares, count)) in statistics[start..start + tile.width as usize].iter().enumerate() {
+                        result.add_samples(offset + i, *luminances, *squares, *count);
+                    }
+                }
+                for (index, sum, weight) in splats.pixels(settings.width) {
+                    let (x, y) = (index as i32 % settings.width, index as i32 / settings.width);
+                    if x >= tile.x && x < tile.x + tile.width && y >= tile.y && y < tile.y + tile.height {
+                        result.add_weighted(index, sum, weight);
+                    } else {
+                        spill.push((index, sum, weight));
+                    }
+                }
+                drop(result);
+                spills.lock().unwrap()[tile_index] = spill;
+            });
+        }
+        pool.join();
+        {
+            let mut result = framebuffer.lock().unwrap();
+            for spill in spills.lock().unwrap().iter() {
+                for (index, sum, weight) in spill.iter() {
+                    result.add_weighted(*index, *sum, *weight);
+                }
+            }
+        }
+        after_pass(framebuffer);
+    }
+}
+
+/// The samples of a tile weighted by the reconstruction filter, for the pixels of the tile and
+/// `margin` pixels around it.
+struct Splats {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    sums: Vec<Color>,
+    weights: Vec<f64>,
+}
+
+impl Splats {
+    fn new(tile: &Tile, margin: i32, image_width: i32, image_height: i32) -> Splats {
+        let x = (tile.x - margin).max(0);
+        let y = (tile.y - margin).max(0);
+        let width = (tile.x + tile.width + margin).min(image_width) - x;
+        let height = (tile.y + tile.height + margin).min(image_height) - y;
+        let size = (width * height) as usize;
+        return Splats { x, y, width, height, sums: vec![Vec3::new(); size], weights: vec![0.0; size] };
+    }
+
+    /// Adds a sample at `x` and `y` in pixels from the top left corner of the image to all pixels
+    /// whose filter reaches it.
+    fn add(&mut self, filter: &Filter, x: f64, y: f64, color: Color) {
+        let radius = filter.radius();
+        let first_x = ((x - radius - 0.5).floor() as i32).max(self.x);
+        let last_x = ((x + radius - 0.5).ceil() as i32).min(self.x + self.width - 1);
+        let first_y = ((y - radius - 0.5).floor() as i32).max(self.y);
+        let last_y = ((y + radius - 0.5).ceil() as i32).min(self.y + self.height - 1);
+        for pixel_y in first_y..=last_y {
+            for pixel_x in first_x..=last_x {
+                let weight = filter.evaluate(pixel_x as f64 + 0.5 - x, pixel_y as f64 + 0.5 - y);
+                if weight != 0.0 {
+                    let i = ((pixel_y - self.y) * self.width + pixel_x - self.x) as usize;
+                    self.sums[i] = self.sums[i] + color * weight;
+                    self.weights[i] += weight;
+                }
+            }
+        }
+    }
+
+    /// The index in the image, the weighted sum and the sum of the weights of every pixel that a
+    /// sample has reached.
+    fn pixels(&self, image_width: i32) -> Vec<(usize, Color, f64)> {
+        let mut pixels = Vec::new();
+        for (i, (sum, weight)) in self.sums.iter().zip(self.weights.iter()).enumerate() {
+            if *weight != 0.0 {
+                let (x, y) = (self.x + i as i32 % self.width, self.y + i as i32 / self.width);
+                pixels.push(((y * image_width + x) as usize, *sum, *weight));
+            }
+        }
+        return pixels;
+    }
+}
+
+/// Renders the requested AOVs, one image per entry of `aovs`. Every pixel averages the first hits
+/// of `samples_per_pixel` camera rays, except for ids which come from a ray through its center.
+pub fn render_aovs(scene: &Scene, world: &Arc<Hittable>, settings: &RenderSettings, aovs: &[Aov]) -> Vec<Image> {
+    let settings = *settings;
+    let cam = scene.camera.with_aspect_ratio(settings.aspect_ratio());
+    let ids = Arc::new(AovIds::new(&scene.hittables));
+    let aovs: Arc<Vec<Aov>> = Arc::new(aovs.to_vec());
+    let images = Arc::new(Mutex::new(vec![Image::new(settings.width, settings.height); aovs.len()]));
+    let pool = ThreadPool::new(settings.threads);
+
+    for tile in spiral_tiles(settings.width, settings.height, settings.tile_size) {
+        let (world, ids, aovs, images) = (world.clone(), ids.clone(), aovs.clone(), images.clone());
+        pool.execute(move || {
+            let mut sampler = Sampler::new(settings.sampler, settings.samples_per_pixel as u32, settings.seed);
+            let mut buffers = vec![Vec::with_capacity((tile.width * tile.height) as usize); aovs.len()];
+            for row in 0..tile.height {
+                let x = settings.height - (tile.y + row) - 1;
+                for j in tile.x..tile.x + tile.width {
+                    let mut sums = vec![Vec3::new(); aovs.len()];
+                    for index in 0..settings.samples_per_pixel as u32 {
+                        sampler.start_sample(j, x, index);
+                        let (offset_u, offset_v) = sampler.get_2d();
+                        let u = (j as f64 + offset_u) / (settings.width as f64 - 1.0);
+                        let v = (x as f64 + offset_v) / (settings.height as f64 - 1.0);
+                        let sample = ids.sample(&world, &cam.get_ray(u, v, &mut sampler));
+                        for (sum, aov) in sums.iter_mut().zip(aovs.iter()) {
+                            *sum = *sum + sample.value(*aov);
+                        }
+                    }
+                    let u = (j as f64 + 0.5) / (settings.width as f64 - 1.0);
+                    let v = (x as f64 + 0.5) / (settings.height as f64 - 1.0);
+                    sampler.start_sample(j, x, 0);
+                    let center = ids.sample(&world, &cam.get_ray(u, v, &mut sampler));
+                    for (index, aov) in aovs.iter().enumerate() {
+                        buffers[index].push(if aov.is_id() {
+                            center.value(*aov)
+                        } else {
+                            sums[index] / settings.samples_per_pixel as f64
+                        });
+                    }
+                }
+            }
+            let mut images = images.lock().unwrap();
+            for (image, buffer) in images.iter_mut().zip(buffers.into_iter()) {
+                for (i, color) in buffer.into_iter().enumerate() {
+                    let i = i as i32;
+                    image.set(tile.x + i % tile.width, tile.y + i / tile.width, color);
+                }
+            }
+        });
+    }
+    pool.join();
+    let images = images.lock().unwrap();
+    return images.clone();
+}
+
+/// Averages the accumulated samples of a framebuffer into an image.
+pub fn resolve_framebuffer(framebuffer: &Framebuffer) -> Image {
+    return framebuffer.lock().unwrap().resolve();
+}
@@ -0,0 +1,32 @@
+use crate::samplers::sampler::{hash, to_unit};
+use crate::samplers::stratified::permutation_element;
+
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109,
+    113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233, 239,
+    241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307, 311,
+];
+
+/// The `index`-th point of dimension `dimension` of the Halton sequence with Owen scrambling
+/// chosen by `seed`: every digit is permuted randomly depending on the digits before it, which
+/// keeps the points well distributed but breaks up the correlation between the higher dimensions
+/// and between pixels. Dimensions beyond the table of primes fall back to random values.
+pub fn halton(dimension: u32, index: u32, seed: u32) -> f64 {
+    let base = match PRIMES.get(dimension as usize) {
+        Some(base) => *base as u64,
+        None => return to_unit(hash(seed, index)),
+    };
+    // Enough digits to represent every 32 bit index, the leading zeros are permuted as well.
+    let digits = (32.0 / (base as f64).log2()).ceil() as u32;
+    let mut index = index as u64;
+    let mut reversed: u64 = 0;
+    let mut scale = 1.0;
+    for _ in 0..digits {
+        let digit = (index % base) as u32;
+        index /= base;
+        let digit = permutation_element(digit, base as u32, hash(seed, reversed as u32 ^ (reversed >> 32) as u32));
+        reversed = reversed * base + digit as u64;
+        scale /= base as f64;
+    }
+    return (reversed as f64 * scale).min(1.0 - f64::EPSILON);
+}
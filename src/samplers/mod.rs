@@ -0,0 +1,5 @@
+pub mod halton;
+pub mod sampler;
+pub mod sobol;
+pub mod stratified;
+pub mod warp;
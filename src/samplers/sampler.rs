@@ -0,0 +1,109 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::samplers::halton::halton;
+use crate::samplers::sobol::{sobol_1d, sobol_2d};
+use crate::samplers::stratified::{stratified_1d, stratified_2d};
+
+/// How a [`Sampler`] chooses its values.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingMethod {
+    /// Independent uniform random numbers from a generator seeded for every sample.
+    Random,
+    /// Jittered strata, shuffled independently for every dimension.
+    Stratified,
+    /// The Halton sequence with Owen scrambling chosen for every pixel.
+    Halton,
+    /// The Sobol sequence with Owen scrambling, shuffled independently for every pair of dimensions.
+    Sobol,
+}
+
+/// Supplies the values in [0, 1) that drive the random decisions of one path, e.g. where in the
+/// pixel the camera ray goes or which direction a material scatters in. Each call of `get_1d` or
+/// `get_2d` moves on to the next dimension, so the n-th decision of every sample of a pixel draws
+/// from the same well distributed point set.
+///
+/// All values are derived from the global seed, the pixel and the index of the sample, so a
+/// render gives the same image no matter which thread takes which sample.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    pub method: SamplingMethod,
+    pub samples_per_pixel: u32,
+    pub seed: u64,
+    pixel_seed: u32,
+    index: u32,
+    dimension: u32,
+    rng: StdRng,
+}
+
+impl Sampler {
+    pub fn new(method: SamplingMethod, samples_per_pixel: u32, seed: u64) -> Sampler {
+        return Sampler {
+            method,
+            samples_per_pixel: samples_per_pixel.max(1),
+            seed,
+            pixel_seed: 0,
+            index: 0,
+            dimension: 0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+    }
+
+    /// Starts the `index`-th sample of the pixel at `x` and `y`.
+    pub fn start_sample(&mut self, x: i32, y: i32, index: u32) {
+        self.pixel_seed = hash(hash(x as u32, y as u32), self.seed as u32 ^ (self.seed >> 32) as u32);
+        self.index = index;
+        self.dimension = 0;
+        if self.method == SamplingMethod::Random {
+            self.rng = StdRng::seed_from_u64(((self.pixel_seed as u64) << 32) | index as u64);
+        }
+    }
+
+    pub fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        let seed = hash(self.pixel_seed, dimension);
+        return match self.method {
+            SamplingMethod::Random => self.rng.gen(),
+            SamplingMethod::Stratified => stratified_1d(self.index, self.samples_per_pixel, seed),
+            SamplingMethod::Halton => halton(dimension, self.index, seed),
+            SamplingMethod::Sobol => sobol_1d(self.index, seed),
+        };
+    }
+
+    pub fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.dimension;
+        self.dimension += 2;
+        let seed = hash(self.pixel_seed, dimension);
+        return match self.method {
+            SamplingMethod::Random => (self.rng.gen(), self.rng.gen()),
+            SamplingMethod::Stratified => stratified_2d(self.index, self.samples_per_pixel, seed),
+            SamplingMethod::Halton => (
+                halton(dimension, self.index, seed),
+                halton(dimension + 1, self.index, hash(seed, 1)),
+            ),
+            SamplingMethod::Sobol => sobol_2d(self.index, seed),
+        };
+    }
+}
+
+/// Mixes the bits of `x`, using the finalizer of MurmurHash3.
+pub fn mix(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^= x >> 16;
+    return x;
+}
+
+pub fn hash(a: u32, b: u32) -> u32 {
+    return mix(a.wrapping_mul(0x9e37_79b9) ^ mix(b.wrapping_add(0x7f4a_7c15)));
+}
+
+/// Maps all 32 bits of `x` to [0, 1).
+pub fn to_unit(x: u32) -> f64 {
+    return x as f64 / 4_294_967_296.0;
+}
@@ -0,0 +1,46 @@
+use crate::samplers::sampler::{hash, to_unit};
+
+/// The `index`-th point of the first Sobol dimension (the van der Corput sequence) with Owen
+/// scrambling chosen by `seed`.
+pub fn sobol_1d(index: u32, seed: u32) -> f64 {
+    let index = nested_uniform_scramble(index, seed);
+    return to_unit(nested_uniform_scramble(index.reverse_bits(), hash(seed, 0)));
+}
+
+/// The `index`-th point of the first two Sobol dimensions with Owen scrambling chosen by `seed`.
+/// The points are shuffled by scrambling the index as well, which keeps them well distributed
+/// but decorrelates pairs of dimensions that use different seeds. This follows Burley,
+/// "Practical Hash-based Owen Scrambling" (2020).
+pub fn sobol_2d(index: u32, seed: u32) -> (f64, f64) {
+    let index = nested_uniform_scramble(index, seed);
+    return (
+        to_unit(nested_uniform_scramble(index.reverse_bits(), hash(seed, 0))),
+        to_unit(nested_uniform_scramble(second_dimension(index), hash(seed, 1))),
+    );
+}
+
+/// The second Sobol dimension, whose generator matrix is Pascal's triangle modulo 2.
+fn second_dimension(mut index: u32) -> u32 {
+    let mut value = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 == 1 {
+            value ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    return value;
+}
+
+/// Owen scrambling: randomly flips every bit depending on the bits above it. The hash by Laine
+/// and Karras only mixes bits upwards, so it is applied to the reversed bits.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    return x.reverse_bits();
+}
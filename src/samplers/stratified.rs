@@ -0,0 +1,60 @@
+use crate::samplers::sampler::{hash, to_unit};
+
+/// The `index`-th of `count` jittered strata of [0, 1). The order of the strata is a random
+/// permutation chosen by `seed`, so different dimensions are not correlated.
+pub fn stratified_1d(index: u32, count: u32, seed: u32) -> f64 {
+    let stratum = permutation_element(index % count, count, seed);
+    return (stratum as f64 + to_unit(hash(seed, index))) / count as f64;
+}
+
+/// Like `stratified_1d` for the unit square, which is divided into a grid of at least `count`
+/// cells that is as close to square as possible.
+pub fn stratified_2d(index: u32, count: u32, seed: u32) -> (f64, f64) {
+    let columns = (count as f64).sqrt().ceil() as u32;
+    let rows = (count + columns - 1) / columns;
+    let stratum = permutation_element(index % count, columns * rows, seed);
+    let x = stratum % columns;
+    let y = stratum / columns;
+    let jitter = hash(seed, index);
+    return (
+        (x as f64 + to_unit(jitter)) / columns as f64,
+        (y as f64 + to_unit(hash(jitter, 1))) / rows as f64,
+    );
+}
+
+/// The `i`-th element of a random permutation of 0 to `length` - 1 chosen by `seed`, without
+/// storing the permutation. This is the hash based permutation by Kensler, "Correlated
+/// Multi-Jittered Sampling" (2013).
+pub fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    // Permute within the next power of two and repeat until the result falls below the length.
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    return (i.wrapping_add(seed)) % length;
+}
@@ -0,0 +1,48 @@
+use std::f64::consts::PI;
+
+use crate::vec3::Vec3;
+
+/// Maps the unit square to the unit disk in the xy-plane with the concentric mapping by Shirley
+/// and Chiu, which keeps neighbouring points close together.
+pub fn sample_disk(u: (f64, f64)) -> Vec3 {
+    let a = 2.0 * u.0 - 1.0;
+    let b = 2.0 * u.1 - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return Vec3::new();
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    return Vec3 { e: [r * theta.cos(), r * theta.sin(), 0.0] };
+}
+
+/// Maps the unit square uniformly to the directions of the unit sphere.
+pub fn sample_sphere(u: (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    return Vec3 { e: [r * phi.cos(), r * phi.sin(), z] };
+}
+
+/// Maps the unit square uniformly to the directions of the hemisphere around the z axis.
+pub fn sample_hemisphere(u: (f64, f64)) -> Vec3 {
+    let z = u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    return Vec3 { e: [r * phi.cos(), r * phi.sin(), z] };
+}
+
+/// Maps the unit square to the directions of the hemisphere around the z axis with a density
+/// proportional to the cosine to the axis, by projecting the disk up onto the hemisphere.
+pub fn sample_cosine_hemisphere(u: (f64, f64)) -> Vec3 {
+    let d = sample_disk(u);
+    let z = (1.0 - d.x() * d.x() - d.y() * d.y()).max(0.0).sqrt();
+    return Vec3 { e: [d.x(), d.y(), z] };
+}
+
+/// Maps the unit cube uniformly to the unit ball.
+pub fn sample_ball(u: (f64, f64), w: f64) -> Vec3 {
+    return sample_sphere(u) * w.cbrt();
+}
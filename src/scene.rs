@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::hittables::hittable::Hittable;
+use crate::lights::environment::Background;
+use crate::lights::light::Light;
+use crate::settings::RenderSettings;
+
+pub struct Scene {
+    pub camera: Camera,
+    pub settings: RenderSettings,
+    pub hittables: Vec<Arc<Hittable>>,
+    /// Lights besides the emissive primitives in `hittables`.
+    pub lights: Vec<Light>,
+    pub background: Background,
+}
+
+impl Scene {
+    pub fn new(camera: Camera, hittables: Vec<Arc<Hittable>>) -> Scene {
+        return Scene {
+            camera,
+            settings: RenderSettings::default(),
+            hittables,
+            lights: Vec::new(),
+            background: Background::default(),
+        };
+    }
+}
@@ -0,0 +1,69 @@
+use crate::color::ToneMapping;
+use crate::filter::Filter;
+use crate::samplers::sampler::SamplingMethod;
+use crate::tiles::default_threads;
+
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+    pub width: i32,
+    pub height: i32,
+    pub samples_per_pixel: usize,
+    /// The number of samples added to every pixel in one pass over the image.
+    pub samples_per_pass: usize,
+    /// With adaptive sampling, pixels stop being sampled once the relative standard error of their
+    /// luminance is below this threshold.
+    pub noise_threshold: Option<f64>,
+    /// The number of samples every pixel gets before adaptive sampling may stop it.
+    pub min_samples_per_pixel: usize,
+    /// How the samples of a pixel are distributed.
+    pub sampler: SamplingMethod,
+    /// The seed all random decisions are derived from, the same seed gives the same image.
+    pub seed: u64,
+    /// How the samples are weighted into the pixels around them.
+    pub filter: Filter,
+    pub depth: i32,
+    pub threads: usize,
+    /// The side length in pixels of the square tiles the image is split into for the threads.
+    pub tile_size: i32,
+    /// Whether paths are terminated randomly once their throughput gets low.
+    pub russian_roulette: bool,
+    /// The number of bounces every path takes before Russian roulette can terminate it.
+    pub min_bounces: i32,
+    /// Whether emissive primitives, area lights and the environment are sampled directly at every
+    /// diffuse or glossy hit. Point, spot and directional lights are always sampled.
+    pub light_sampling: bool,
+    /// How the image is converted for display and for 8 and 16 bit files.
+    pub tone_mapping: ToneMapping,
+    /// Whether the image is filtered with the denoiser guided by albedo, normal and depth.
+    pub denoise: bool,
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        return self.width as f64 / self.height as f64;
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 500,
+            height: 281,
+            samples_per_pixel: 200,
+            samples_per_pass: 16,
+            noise_threshold: None,
+            min_samples_per_pixel: 16,
+            sampler: SamplingMethod::Sobol,
+            seed: 0,
+            filter: Filter::default(),
+            depth: 100,
+            threads: default_threads(),
+            tile_size: 32,
+            russian_roulette: true,
+            min_bounces: 3,
+            light_sampling: true,
+            tone_mapping: ToneMapping::default(),
+            denoise: false,
+        }
+    }
+}
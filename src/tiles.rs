@@ -0,0 +1,51 @@
+/// A rectangle of pixels rendered as one job. `y` counts rows from the top of the image.
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Tile {
+    /// The index of the first pixel of row `row` of the tile in a row by row image.
+    pub fn row_offset(&self, row: i32, image_width: i32) -> usize {
+        return ((self.y + row) * image_width + self.x) as usize;
+    }
+}
+
+/// Covers the image with square tiles of `size` pixels, cut off at the right and bottom edges. The
+/// tiles are ordered in a spiral from the center outwards, so the usually more interesting middle
+/// of the image is finished first and neighbouring tiles are rendered close together in time.
+pub fn spiral_tiles(width: i32, height: i32, size: i32) -> Vec<Tile> {
+    let size = size.max(1);
+    let columns = (width + size - 1) / size;
+    let rows = (height + size - 1) / size;
+    let center_x = (columns - 1) as f64 / 2.0;
+    let center_y = (rows - 1) as f64 / 2.0;
+
+    let mut tiles = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            tiles.push(Tile {
+                x: column * size,
+                y: row * size,
+                width: size.min(width - column * size),
+                height: size.min(height - row * size),
+            });
+        }
+    }
+    // Sort by the square ring around the center, then by the angle within the ring.
+    let key = |tile: &Tile| {
+        let dx = (tile.x / size) as f64 - center_x;
+        let dy = (tile.y / size) as f64 - center_y;
+        return (dx.abs().max(dy.abs()), dy.atan2(dx));
+    };
+    tiles.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+    return tiles;
+}
+
+/// The number of worker threads used when none is configured: one per available core.
+pub fn default_threads() -> usize {
+    return std::thread::available_parallelism().map_or(1, |threads| threads.get());
+}
@@ -0,0 +1,86 @@
+/// A piecewise constant distribution over [0, 1) with one segment per function value, sampled by
+/// inverting its cumulative distribution.
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    func_int: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Distribution1D {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].abs() / n as f64;
+        }
+        let func_int = cdf[n];
+        for i in 1..=n {
+            cdf[i] = if func_int == 0.0 { i as f64 / n as f64 } else { cdf[i] / func_int };
+        }
+        return Distribution1D { func, cdf, func_int };
+    }
+
+    pub fn integral(&self) -> f64 {
+        return self.func_int;
+    }
+
+    /// Maps `u` in [0, 1) to a sample, returning the sample, its density and its segment.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // Find the last entry of the cdf that is not larger than u.
+        let mut first = 0;
+        let mut last = self.func.len();
+        while first + 1 < last {
+            let middle = (first + last) / 2;
+            if self.cdf[middle] <= u {
+                first = middle;
+            } else {
+                last = middle;
+            }
+        }
+        let offset = first;
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x = (offset as f64 + du) / self.func.len() as f64;
+        return (x.min(1.0 - f64::EPSILON), self.pdf_of(offset), offset);
+    }
+
+    pub fn pdf(&self, x: f64) -> f64 {
+        let offset = ((x * self.func.len() as f64) as usize).min(self.func.len() - 1);
+        return self.pdf_of(offset);
+    }
+
+    fn pdf_of(&self, offset: usize) -> f64 {
+        if self.func_int == 0.0 {
+            return 1.0;
+        }
+        return self.func[offset].abs() / self.func_int;
+    }
+}
+
+/// A piecewise constant distribution over [0, 1)² given by a function on a grid of rows, sampled
+/// by choosing a row from the marginal distribution and then a column in that row.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(rows: Vec<Vec<f64>>) -> Distribution2D {
+        let conditional: Vec<Distribution1D> = rows.into_iter().map(Distribution1D::new).collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|row| row.integral()).collect());
+        return Distribution2D { conditional, marginal };
+    }
+
+    /// Returns the sampled (u, v) coordinates, where v selects the row, and their density.
+    pub fn sample(&self, u1: f64, u2: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(u2);
+        let (u, pdf_u, _) = self.conditional[row].sample(u1);
+        return ((u, v), pdf_u * pdf_v);
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let rows = self.conditional.len();
+        let row = ((v * rows as f64) as usize).min(rows - 1);
+        return self.marginal.pdf(v) * self.conditional[row].pdf(u);
+    }
+}
@@ -14,10 +14,6 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     return x;
 }
 
-pub fn random_double(min: f64, max: f64) -> f64 {
-    return min + (max - min) * rand::random::<f64>();
-}
-
 pub fn binary_split(vec: &Vec<u64>, first: usize, last: usize) -> usize {
     let first_code = vec[first];
     let last_code = vec[last];
@@ -1,2 +1,4 @@
+pub mod distribution;
 pub mod math_utils;
 pub mod morton_code;
+pub mod onb;
@@ -0,0 +1,42 @@
+use crate::vec3::{cross, dot, Vec3};
+
+/// A right-handed orthonormal basis around the direction `w`, so `u x v = w`, used to express
+/// directions relative to a surface normal or to the axis of a cone.
+#[derive(Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vec3) -> Onb {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3 { e: [0.0, 1.0, 0.0] }
+        } else {
+            Vec3 { e: [1.0, 0.0, 0.0] }
+        };
+        let v = cross(&w, &a).unit_vector();
+        let u = cross(&v, &w);
+        return Onb { u, v, w };
+    }
+
+    /// The world space direction with the coordinates `a`, `b` and `c` along `u`, `v` and `w`.
+    pub fn local(&self, a: f64, b: f64, c: f64) -> Vec3 {
+        return self.u * a + self.v * b + self.w * c;
+    }
+
+    /// Converts a direction from the coordinates of this basis to world space, the inverse of
+    /// `to_local`.
+    pub fn to_world(&self, d: &Vec3) -> Vec3 {
+        return self.local(d.x(), d.y(), d.z());
+    }
+
+    /// Converts a direction from world space to the coordinates of this basis.
+    pub fn to_local(&self, d: &Vec3) -> Vec3 {
+        return Vec3 {
+            e: [dot(d, &self.u), dot(d, &self.v), dot(d, &self.w)],
+        };
+    }
+}
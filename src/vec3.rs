@@ -1,16 +1,15 @@
-use crate::utils::math_utils::random_double;
 use std::{fmt, ops};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub struct Vec3 {
-    pub(crate) e: [f64; 3],
+    pub e: [f64; 3],
 }
 
 impl Vec3 {
-    pub(crate) fn new() -> Vec3 {
+    pub fn new() -> Vec3 {
         return Vec3 { e: [0.0, 0.0, 0.0] };
     }
-    pub(crate) fn unit_vector(self) -> Vec3 {
+    pub fn unit_vector(self) -> Vec3 {
         return self / self.length();
     }
 
@@ -32,6 +31,10 @@ impl Vec3 {
         return Vec3{e: [x, y, z]}.unit_vector()
     }
 
+    pub fn max_component(self) -> f64 {
+        return self.e[0].max(self.e[1]).max(self.e[2]);
+    }
+
     pub fn x(self) -> f64 {
         return self.e[0];
     }
@@ -174,36 +177,6 @@ impl ops::Neg for Vec3 {
     }
 }
 
-fn random(min: f64, max: f64) -> Vec3 {
-    return Vec3 {
-        e: [
-            random_double(min, max),
-            random_double(min, max),
-            random_double(min, max),
-        ],
-    };
-}
-
-pub fn random_in_unit_sphere() -> Vec3 {
-    let mut p;
-    loop {
-        p = random(-1.0, 1.0);
-        if p.length_squared() >= 1.0 {
-            continue;
-        }
-        break;
-    }
-    return p;
-}
-
-pub fn random_in_hemisphere(normal: &Vec3) -> Vec3 {
-    let in_unit_sphere = random_in_unit_sphere();
-    return if dot(&in_unit_sphere, normal) > 0.0 {
-        in_unit_sphere
-    } else {
-        -in_unit_sphere
-    };
-}
 pub fn refract(uv: &Vec3, n: &Vec3, etai_over_etat: f64) -> Vec3 {
     let cos_theta = dot(&-*uv, n).min(1.0);
     let r_out_perp = (*uv + *n * cos_theta) * etai_over_etat;
@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::image::Image;
+
+/// Writes the linear values of the image as a single part, scanline based OpenEXR file without
+/// compression. The channels are stored as 32 bit floats.
+pub fn write_exr(image: &Image, path: &Path) -> std::io::Result<()> {
+    let width = image.width;
+    let height = image.height;
+    let mut header = Vec::new();
+    // The magic number and version 2 without any flags.
+    header.extend_from_slice(&20000630i32.to_le_bytes());
+    header.extend_from_slice(&2i32.to_le_bytes());
+
+    // Channels have to be sorted by name.
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"].iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&2i32.to_le_bytes()); // FLOAT
+        channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+        channels.extend_from_slice(&1i32.to_le_bytes()); // xSampling
+        channels.extend_from_slice(&1i32.to_le_bytes()); // ySampling
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(&mut header, "compression", "compression", &[0]);
+    let window = box2i(0, 0, width - 1, height - 1);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    header.push(0);
+
+    // Every scanline is its own chunk, found through a table of offsets after the header.
+    let line_size = width as usize * 3 * 4;
+    let chunk_size = (8 + line_size) as u64;
+    let first_chunk = (header.len() + 8 * height as usize) as u64;
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(&header)?;
+    for y in 0..height as u64 {
+        file.write_all(&(first_chunk + y * chunk_size).to_le_bytes())?;
+    }
+    for y in 0..height {
+        file.write_all(&y.to_le_bytes())?;
+        file.write_all(&(line_size as i32).to_le_bytes())?;
+        for channel in [2, 1, 0].iter() {
+            for x in 0..width {
+                file.write_all(&(image.get(x, y).e[*channel] as f32).to_le_bytes())?;
+            }
+        }
+    }
+    return file.flush();
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn box2i(x_min: i32, y_min: i32, x_max: i32, y_max: i32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for v in [x_min, y_min, x_max, y_max].iter() {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    return bytes;
+}
@@ -0,0 +1,40 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::image::Image;
+use crate::vec3::Color;
+
+/// Writes the linear values of the image as an uncompressed Radiance RGBE file, the format read
+/// by [`crate::parsers::hdr::read_hdr`].
+pub fn write_hdr(image: &Image, path: &Path) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", image.height, image.width)?;
+    for pixel in image.pixels.iter() {
+        file.write_all(&color_to_rgbe(pixel))?;
+    }
+    return file.flush();
+}
+
+/// Stores the three channels with 8 bit mantissas and a shared exponent.
+fn color_to_rgbe(color: &Color) -> [u8; 4] {
+    let r = color.x().max(0.0);
+    let g = color.y().max(0.0);
+    let b = color.z().max(0.0);
+    let max = r.max(g).max(b);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // max = m * 2^exponent with m in [0.5, 1).
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2f64.powi(exponent);
+    return [
+        (r * scale).min(255.0) as u8,
+        (g * scale).min(255.0) as u8,
+        (b * scale).min(255.0) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ];
+}
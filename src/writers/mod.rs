@@ -0,0 +1,3 @@
+pub mod exr;
+pub mod hdr;
+pub mod pfm;
@@ -0,0 +1,20 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::image::Image;
+
+/// Writes the linear values of the image as a color Portable Float Map. The rows of a PFM file
+/// are stored from the bottom, the values as little endian 32 bit floats.
+pub fn write_pfm(image: &Image, path: &Path) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "PF\n{} {}\n-1.0\n", image.width, image.height)?;
+    for y in (0..image.height).rev() {
+        for x in 0..image.width {
+            for c in image.get(x, y).e.iter() {
+                file.write_all(&(*c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    return file.flush();
+}
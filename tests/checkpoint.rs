@@ -0,0 +1,104 @@
+//! A checkpoint must restore the accumulated samples exactly, and only be resumed by a render
+//! whose samples add up with them.
+
+use std::fs;
+use std::path::PathBuf;
+
+use raytracer::checkpoint::{read_checkpoint, write_checkpoint};
+use raytracer::filter::Filter;
+use raytracer::renderer::Accumulation;
+use raytracer::samplers::sampler::SamplingMethod;
+use raytracer::vec3::Color;
+use raytracer::RenderSettings;
+
+/// A path in the temporary directory that no other test run uses.
+fn temporary_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name));
+}
+
+fn settings() -> RenderSettings {
+    let mut settings = RenderSettings::default();
+    settings.width = 5;
+    settings.height = 3;
+    settings.samples_per_pixel = 64;
+    settings.seed = 0x1234_5678_9abc;
+    settings.sampler = SamplingMethod::Stratified;
+    settings.filter = Filter::Gaussian { radius: 1.25 };
+    return settings;
+}
+
+/// Accumulated samples that differ in every pixel and every field.
+fn accumulation(settings: &RenderSettings) -> Accumulation {
+    let mut accumulation = Accumulation::new(settings.width, settings.height);
+    for i in 0..accumulation.sums.len() {
+        let value = i as f64 + 0.1;
+        accumulation.sums[i] = Color { e: [value, value * 1e-9, -value / 3.0] };
+        accumulation.weights[i] = value * 0.7;
+        accumulation.luminances[i] = value * 11.0;
+        accumulation.squares[i] = value * value;
+        accumulation.samples[i] = i as u32 * 3;
+    }
+    return accumulation;
+}
+
+#[test]
+fn checkpoint_round_trip() {
+    let settings = settings();
+    let written = accumulation(&settings);
+    let path = temporary_path("round-trip.ckpt");
+    write_checkpoint(&written, &settings, &path).unwrap();
+    let checkpoint = read_checkpoint(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(checkpoint.seed, settings.seed);
+    assert_eq!(checkpoint.sampler, settings.sampler);
+    assert_eq!(checkpoint.samples_per_pixel, settings.samples_per_pixel);
+    assert_eq!(checkpoint.filter, settings.filter);
+    let read = &checkpoint.accumulation;
+    assert_eq!((read.width, read.height), (written.width, written.height));
+    for i in 0..written.sums.len() {
+        assert_eq!(read.sums[i].e, written.sums[i].e);
+        assert_eq!(read.weights[i], written.weights[i]);
+        assert_eq!(read.luminances[i], written.luminances[i]);
+        assert_eq!(read.squares[i], written.squares[i]);
+        assert_eq!(read.samples[i], written.samples[i]);
+    }
+    assert!(checkpoint.check(&settings).is_ok());
+}
+
+#[test]
+fn checkpoint_rejects_other_settings() {
+    let settings = settings();
+    let path = temporary_path("mismatch.ckpt");
+    write_checkpoint(&accumulation(&settings), &settings, &path).unwrap();
+    let checkpoint = read_checkpoint(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let changes: [(&str, fn(&mut RenderSettings)); 5] = [
+        ("size", |s| s.width = 6),
+        ("seed", |s| s.seed += 1),
+        ("sampler", |s| s.sampler = SamplingMethod::Sobol),
+        ("samples per pixel", |s| s.samples_per_pixel = 128),
+        ("filter", |s| s.filter = Filter::Gaussian { radius: 1.5 }),
+    ];
+    for (name, change) in changes.iter() {
+        let mut other = settings;
+        change(&mut other);
+        assert!(checkpoint.check(&other).is_err(), "a checkpoint with another {} was accepted", name);
+    }
+}
+
+#[test]
+fn checkpoint_takes_more_samples_unless_stratified() {
+    // The other samplers give the same first samples for any count, so a finished render can be
+    // refined with more of them.
+    let mut settings = settings();
+    settings.sampler = SamplingMethod::Sobol;
+    let path = temporary_path("more-samples.ckpt");
+    write_checkpoint(&accumulation(&settings), &settings, &path).unwrap();
+    let checkpoint = read_checkpoint(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    settings.samples_per_pixel *= 4;
+    assert!(checkpoint.check(&settings).is_ok());
+}
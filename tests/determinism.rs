@@ -0,0 +1,78 @@
+//! The same seed has to give the same image, no matter how many threads render it and in which
+//! order they finish their tiles.
+
+use std::sync::Arc;
+
+use raytracer::filter::Filter;
+use raytracer::hittables::hittable::Hittable;
+use raytracer::hittables::sphere::Sphere;
+use raytracer::lights::environment::Background;
+use raytracer::lights::light::Light;
+use raytracer::vec3::{Color, Vec3};
+use raytracer::{create_camera, render, Image, Material, Scene, Texture};
+
+fn sphere(position: Vec3, radius: f64, material: Material) -> Arc<Hittable> {
+    return Arc::new(Hittable::Sphere { sphere: Sphere { position, radius, material: Arc::new(material) } });
+}
+
+/// A diffuse and a glossy sphere on a floor under a rectangular light and a sky, rendered in
+/// many small tiles whose filtered samples reach into each other.
+fn render_with_threads(threads: usize) -> Image {
+    let grey = Material::Diffuse { albedo: Texture::Solid { color: Color { e: [0.6, 0.6, 0.6] } }, emission: Vec3::new() };
+    let red = Material::Diffuse { albedo: Texture::Solid { color: Color { e: [0.8, 0.2, 0.1] } }, emission: Vec3::new() };
+    let glossy = Material::CookTorrance {
+        diffuse: Texture::Solid { color: Color { e: [0.2, 0.3, 0.8] } },
+        k_d: 0.4,
+        specular: Texture::Solid { color: Color { e: [0.9, 0.9, 0.9] } },
+        roughness: 0.3,
+        emission: Vec3::new(),
+    };
+    let hittables = vec![
+        sphere(Vec3 { e: [0.0, -100.0, 0.0] }, 100.0, grey),
+        sphere(Vec3 { e: [-0.6, 0.5, 0.0] }, 0.5, red),
+        sphere(Vec3 { e: [0.6, 0.5, 0.0] }, 0.5, glossy),
+    ];
+    let camera = create_camera(
+        Vec3 { e: [0.0, 1.0, 4.0] },
+        Vec3 { e: [0.0, 0.5, 0.0] },
+        Vec3 { e: [0.0, 1.0, 0.0] },
+        40.0,
+        1.0,
+        4.0,
+        0.0,
+    );
+    let mut scene = Scene::new(camera, hittables);
+    scene.background = Background::Gradient {
+        bottom: Color { e: [1.0, 1.0, 1.0] },
+        top: Color { e: [0.5, 0.7, 1.0] },
+    };
+    scene.lights.push(Light::Rect {
+        corner: Vec3 { e: [-1.0, 3.0, -1.0] },
+        edge_u: Vec3 { e: [2.0, 0.0, 0.0] },
+        edge_v: Vec3 { e: [0.0, 0.0, 2.0] },
+        emission: Color { e: [4.0, 4.0, 4.0] },
+    });
+    scene.settings.width = 23;
+    scene.settings.height = 17;
+    scene.settings.samples_per_pixel = 32;
+    scene.settings.samples_per_pass = 4;
+    scene.settings.noise_threshold = Some(0.05);
+    scene.settings.min_samples_per_pixel = 8;
+    scene.settings.filter = Filter::Gaussian { radius: 1.5 };
+    scene.settings.tile_size = 4;
+    scene.settings.seed = 7;
+    scene.settings.threads = threads;
+    return render(&scene, &scene.settings);
+}
+
+#[test]
+fn thread_count_does_not_change_the_image() {
+    let single = render_with_threads(1);
+    for threads in [2, 4, 7] {
+        let parallel = render_with_threads(threads);
+        assert_eq!((parallel.width, parallel.height), (single.width, single.height));
+        for (i, (a, b)) in single.pixels.iter().zip(parallel.pixels.iter()).enumerate() {
+            assert_eq!(a.e, b.e, "pixel {} differs with {} threads", i, threads);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! The reconstruction filters must stay within their radius, and their shapes must weight the
+//! samples as described.
+
+use raytracer::filter::{Filter, FilterKind};
+
+const KINDS: [FilterKind; 5] = [
+    FilterKind::Box,
+    FilterKind::Tent,
+    FilterKind::Gaussian,
+    FilterKind::Mitchell,
+    FilterKind::Lanczos,
+];
+
+/// The filters with their default radius and with a wider and a narrower one.
+fn filters() -> Vec<Filter> {
+    let mut filters = Vec::new();
+    for kind in KINDS.iter() {
+        let filter = Filter::with_default_radius(*kind);
+        filters.push(filter);
+        filters.push(filter.with_radius(filter.radius() * 1.7));
+        filters.push(filter.with_radius(filter.radius() * 0.6));
+    }
+    return filters;
+}
+
+/// The filter along one axis. The filters are separable, so `evaluate(x, 0)` is that times the
+/// value at the center.
+fn profile(filter: &Filter, x: f64) -> f64 {
+    return filter.evaluate(x, 0.0) / filter.evaluate(0.0, 0.0).sqrt();
+}
+
+/// The integral of the filter along one axis with the midpoint rule.
+fn integral(filter: &Filter) -> f64 {
+    let radius = filter.radius();
+    let steps = 100_000;
+    let step = 2.0 * radius / steps as f64;
+    return (0..steps).map(|i| profile(filter, -radius + (i as f64 + 0.5) * step)).sum::<f64>() * step;
+}
+
+#[test]
+fn filters_are_zero_beyond_their_radius() {
+    for filter in filters().iter() {
+        let radius = filter.radius();
+        for offset in [radius, radius * 1.001, radius + 0.5, radius * 3.0] {
+            for (x, y) in [(offset, 0.0), (-offset, 0.0), (0.0, offset), (0.0, -offset), (offset, offset)] {
+                // The box is half open so a sample on the border between two pixels only counts
+                // for one of them.
+                if matches!(filter, Filter::Box { .. }) && offset == radius && (x == radius || y == radius) {
+                    continue;
+                }
+                assert_eq!(filter.evaluate(x, y), 0.0, "{:?} is not zero at {}, {}", filter, x, y);
+            }
+        }
+        assert!(filter.evaluate(0.0, 0.0) > 0.0, "{:?} is not positive at the center", filter);
+    }
+}
+
+#[test]
+fn box_tent_and_gaussian_are_normalised() {
+    for filter in filters().iter() {
+        if !matches!(filter, Filter::Box { .. } | Filter::Tent { .. } | Filter::Gaussian { .. }) {
+            continue;
+        }
+        let area = integral(filter);
+        assert!((area - 1.0).abs() < 1e-6, "{:?} integrates to {} along an axis", filter, area);
+        // The filters are separable, so they integrate to one in two dimensions as well.
+        assert!((filter.evaluate(0.3, -0.2) - profile(filter, 0.3) * profile(filter, -0.2)).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn mitchell_and_lanczos_have_negative_lobes() {
+    for filter in filters().iter() {
+        if !matches!(filter, Filter::Mitchell { .. } | Filter::Lanczos { .. }) {
+            continue;
+        }
+        let radius = filter.radius();
+        let lowest = (1..1000)
+            .map(|i| profile(filter, radius * i as f64 / 1000.0))
+            .fold(f64::INFINITY, f64::min);
+        assert!(lowest < 0.0, "{:?} has no negative lobe", filter);
+        // The lobes sharpen the image, but the weights of a pixel still add up to more than zero.
+        assert!(integral(filter) > 0.0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Linear images must survive being written and read again, up to the precision of the format.
+
+use std::fs;
+use std::path::PathBuf;
+
+use raytracer::image::Image;
+use raytracer::parsers::hdr::read_hdr;
+use raytracer::vec3::Color;
+use raytracer::writers::exr::write_exr;
+use raytracer::writers::hdr::write_hdr;
+use raytracer::writers::pfm::write_pfm;
+
+/// A path in the temporary directory that no other test run uses.
+fn temporary_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name));
+}
+
+/// An image with values from very dark to far brighter than white, different in every channel.
+fn test_image() -> Image {
+    let mut image = Image::new(9, 4);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let value = 2f64.powi(x - 4) * (1.0 + y as f64 / 3.0);
+            image.set(x, y, Color { e: [value, value * 0.5, value * 0.25 + 0.01] });
+        }
+    }
+    return image;
+}
+
+/// Reads a color PFM file, whose rows are stored from the bottom.
+fn read_pfm(bytes: &[u8]) -> Image {
+    let mut lines = bytes.splitn(4, |byte| *byte == b'\n');
+    assert_eq!(lines.next().unwrap(), b"PF");
+    let size = String::from_utf8(lines.next().unwrap().to_vec()).unwrap();
+    let size: Vec<i32> = size.split_whitespace().map(|v| v.parse().unwrap()).collect();
+    let scale: f64 = String::from_utf8(lines.next().unwrap().to_vec()).unwrap().trim().parse().unwrap();
+    assert!(scale < 0.0, "the values are not little endian");
+    let data = lines.next().unwrap();
+    assert_eq!(data.len(), (size[0] * size[1] * 3 * 4) as usize);
+
+    let mut image = Image::new(size[0], size[1]);
+    let values: Vec<f64> = data.chunks(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f64).collect();
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let i = (((image.height - 1 - y) * image.width + x) * 3) as usize;
+            image.set(x, y, Color { e: [values[i], values[i + 1], values[i + 2]] });
+        }
+    }
+    return image;
+}
+
+fn assert_close(read: &Image, written: &Image, relative: f64) {
+    assert_eq!((read.width, read.height), (written.width, written.height));
+    for (a, b) in read.pixels.iter().zip(written.pixels.iter()) {
+        for c in 0..3 {
+            let tolerance = b.max_component() * relative;
+            assert!((a.e[c] - b.e[c]).abs() <= tolerance, "read {} but wrote {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn pfm_round_trip() {
+    let image = test_image();
+    let path = temporary_path("round-trip.pfm");
+    write_pfm(&image, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    // 32 bit floats keep 24 bits of the mantissa.
+    assert_close(&read_pfm(&bytes), &image, 1e-7);
+}
+
+#[test]
+fn hdr_round_trip() {
+    let image = test_image();
+    let path = temporary_path("round-trip.hdr");
+    write_hdr(&image, &path).unwrap();
+    let read = read_hdr(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    // The channels share an exponent and keep 8 bits each.
+    assert_close(&read, &image, 1.0 / 128.0);
+}
+
+#[test]
+fn hdr_reads_run_length_encoded_scanlines() {
+    let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 10\n".to_vec();
+    // A run-length encoded scanline: the marker and the width, then every channel on its own as
+    // runs (count above 128) and literal spans.
+    bytes.extend_from_slice(&[2, 2, 0, 10]);
+    bytes.extend_from_slice(&[128 + 6, 128, 4, 10, 20, 30, 40]);
+    bytes.extend_from_slice(&[10, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    bytes.extend_from_slice(&[128 + 10, 64]);
+    bytes.extend_from_slice(&[128 + 3, 129, 128 + 7, 130]);
+    // A flat scanline.
+    for _ in 0..10 {
+        bytes.extend_from_slice(&[100, 50, 25, 130]);
+    }
+    let path = temporary_path("rle.hdr");
+    fs::write(&path, &bytes).unwrap();
+    let image = read_hdr(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let rgbe = |r: f64, g: f64, b: f64, e: i32| {
+        let scale = 2f64.powi(e - 136);
+        Color { e: [(r + 0.5) * scale, (g + 0.5) * scale, (b + 0.5) * scale] }
+    };
+    let red = [128.0, 128.0, 128.0, 128.0, 128.0, 128.0, 10.0, 20.0, 30.0, 40.0];
+    for x in 0..10 {
+        let exponent = if x < 3 { 129 } else { 130 };
+        assert_eq!(image.get(x, 0).e, rgbe(red[x as usize], x as f64, 64.0, exponent).e);
+        assert_eq!(image.get(x, 1).e, rgbe(100.0, 50.0, 25.0, 130).e);
+    }
+}
+
+#[test]
+fn hdr_rejects_invalid_sizes() {
+    for resolution in ["-Y -5 +X -3", "-Y 0 +X 10", "-Y 100000 +X 100000"].iter() {
+        let path = temporary_path("invalid-size.hdr");
+        fs::write(&path, format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution)).unwrap();
+        let result = read_hdr(&path);
+        fs::remove_file(&path).unwrap();
+        let error = result.err().expect("an invalid size was accepted");
+        assert_eq!(error.to_string(), "invalid image size", "for {}", resolution);
+    }
+}
+
+#[test]
+fn exr_has_a_valid_header_and_size() {
+    let image = test_image();
+    let path = temporary_path("header.exr");
+    write_exr(&image, &path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let i32_at = |at: usize| i32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    assert_eq!(i32_at(0), 20000630, "wrong magic number");
+    assert_eq!(i32_at(4), 2, "wrong version or flags");
+
+    // The attributes are a name, a type, a size and a value each, up to an empty name.
+    let c_string = |at: usize| {
+        let end = at + bytes[at..].iter().position(|byte| *byte == 0).unwrap();
+        (String::from_utf8(bytes[at..end].to_vec()).unwrap(), end + 1)
+    };
+    let mut position = 8;
+    let mut attributes = Vec::new();
+    loop {
+        let (name, next) = c_string(position);
+        if name.is_empty() {
+            position = next;
+            break;
+        }
+        let (kind, next) = c_string(next);
+        let size = i32_at(next) as usize;
+        attributes.push((name, kind, bytes[next + 4..next + 4 + size].to_vec()));
+        position = next + 4 + size;
+    }
+    let names: Vec<&str> = attributes.iter().map(|(name, _, _)| name.as_str()).collect();
+    for required in ["channels", "compression", "dataWindow", "displayWindow", "lineOrder", "pixelAspectRatio"].iter() {
+        assert!(names.contains(required), "the header misses {}", required);
+    }
+    let (_, kind, window) = attributes.iter().find(|(name, _, _)| name == "dataWindow").unwrap();
+    assert_eq!(kind, "box2i");
+    let window: Vec<i32> = window.chunks(4).map(|c| i32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+    assert_eq!(window, vec![0, 0, image.width - 1, image.height - 1]);
+
+    // An offset table, then one chunk per scanline with its y, its size and three float channels.
+    let line_size = image.width as usize * 3 * 4;
+    let first_chunk = position + 8 * image.height as usize;
+    assert_eq!(bytes.len(), first_chunk + image.height as usize * (8 + line_size));
+    assert_eq!(i32_at(position) as usize, first_chunk);
+    assert_eq!(i32_at(first_chunk), 0);
+    assert_eq!(i32_at(first_chunk + 4) as usize, line_size);
+    // The channels are sorted by name, so red comes last.
+    let red = &bytes[first_chunk + 8 + 2 * line_size / 3..];
+    let red = f32::from_le_bytes([red[0], red[1], red[2], red[3]]);
+    assert_eq!(red as f64, image.get(0, 0).x() as f32 as f64);
+}
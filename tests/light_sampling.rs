@@ -0,0 +1,75 @@
+//! Sampling the lights directly must only reduce the noise of a render, never change what it
+//! converges to.
+
+use std::sync::Arc;
+
+use raytracer::hittables::hittable::Hittable;
+use raytracer::hittables::sphere::Sphere;
+use raytracer::lights::environment::Background;
+use raytracer::lights::light::Light;
+use raytracer::vec3::{Color, Vec3};
+use raytracer::{create_camera, render, Material, Scene, Texture};
+
+/// A rectangular light above the floor, facing down.
+fn rect_light(height: f64, size: f64) -> Light {
+    return Light::Rect {
+        corner: Vec3 { e: [-size / 2.0, height, -size / 2.0] },
+        edge_u: Vec3 { e: [size, 0.0, 0.0] },
+        edge_v: Vec3 { e: [0.0, 0.0, size] },
+        emission: Color { e: [1.0, 1.0, 1.0] },
+    };
+}
+
+/// The mean of all pixels of a rough Cook-Torrance floor lit by the lights.
+fn mean_brightness(lights: Vec<Light>, light_sampling: bool) -> f64 {
+    let floor = Sphere {
+        position: Vec3 { e: [0.0, -1000.0, 0.0] },
+        radius: 1000.0,
+        material: Arc::new(Material::CookTorrance {
+            diffuse: Texture::Solid { color: Color { e: [0.5, 0.5, 0.5] } },
+            k_d: 0.3,
+            specular: Texture::Solid { color: Color { e: [0.9, 0.9, 0.9] } },
+            roughness: 0.6,
+            emission: Vec3::new(),
+        }),
+    };
+    let camera = create_camera(
+        Vec3 { e: [0.0, 1.0, 4.0] },
+        Vec3::new(),
+        Vec3 { e: [0.0, 1.0, 0.0] },
+        40.0,
+        1.0,
+        4.0,
+        0.0,
+    );
+    let mut scene = Scene::new(camera, vec![Arc::new(Hittable::Sphere { sphere: floor })]);
+    scene.background = Background::Solid { color: Vec3::new() };
+    scene.lights = lights;
+    scene.settings.width = 24;
+    scene.settings.height = 24;
+    scene.settings.samples_per_pixel = 256;
+    scene.settings.light_sampling = light_sampling;
+
+    let image = render(&scene, &scene.settings);
+    return image.pixels.iter().map(|pixel| pixel.x()).sum::<f64>() / image.pixels.len() as f64;
+}
+
+#[test]
+fn light_sampling_keeps_rough_surfaces_as_bright() {
+    let sampled = mean_brightness(vec![rect_light(3.0, 4.0)], true);
+    let unsampled = mean_brightness(vec![rect_light(3.0, 4.0)], false);
+    assert!(sampled > 0.0);
+    let ratio = sampled / unsampled;
+    assert!((ratio - 1.0).abs() < 0.02, "light sampling renders {} times as bright", ratio);
+}
+
+#[test]
+fn area_lights_shadow_each_other() {
+    // The lower light faces away from the upper one, so the floor only sees the upper light
+    // around it, whether it is found by sampling or by following the material.
+    let lights = || vec![rect_light(3.0, 4.0), rect_light(2.0, 2.0)];
+    let sampled = mean_brightness(lights(), true);
+    let unsampled = mean_brightness(lights(), false);
+    let ratio = sampled / unsampled;
+    assert!((ratio - 1.0).abs() < 0.02, "light sampling renders {} times as bright", ratio);
+}
@@ -0,0 +1,33 @@
+//! Materials sample and evaluate directions in a local frame around the normal, which has to be
+//! converted to world space and back without changing them.
+
+use raytracer::utils::onb::Onb;
+use raytracer::vec3::{cross, dot, Vec3};
+
+fn assert_close(a: &Vec3, b: &Vec3) {
+    assert!((*a - *b).length() < 1e-12, "{} differs from {}", a, b);
+}
+
+#[test]
+fn bases_are_right_handed_and_convert_both_ways() {
+    let normals = [
+        Vec3 { e: [0.0, 0.0, 1.0] },
+        Vec3 { e: [0.0, -1.0, 0.0] },
+        Vec3 { e: [1.0, 0.0, 0.0] },
+        Vec3 { e: [-0.95, 0.1, 0.3] },
+        Vec3 { e: [0.3, -0.5, -0.8] },
+    ];
+    for normal in normals.iter() {
+        let onb = Onb::from_w(normal);
+        assert_close(&onb.w, &normal.unit_vector());
+        assert_close(&cross(&onb.u, &onb.v), &onb.w);
+        assert!(dot(&onb.u, &onb.v).abs() < 1e-12 && dot(&onb.u, &onb.w).abs() < 1e-12);
+
+        let local = Vec3 { e: [0.6, -0.3, 0.74] }.unit_vector();
+        let world = onb.to_world(&local);
+        assert_close(&onb.to_local(&world), &local);
+        assert!((dot(&world, &onb.w) - local.z()).abs() < 1e-12, "the local z is not the cosine to the normal");
+        let world = Vec3 { e: [-0.2, 0.9, 0.4] };
+        assert_close(&onb.to_world(&onb.to_local(&world)), &world);
+    }
+}
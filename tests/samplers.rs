@@ -0,0 +1,133 @@
+//! The samplers must give values in [0, 1) that spread evenly over a pixel, and the same values
+//! for a sample no matter which thread takes it or what that thread sampled before.
+
+use std::collections::HashSet;
+use std::thread;
+
+use raytracer::samplers::sampler::{Sampler, SamplingMethod};
+
+const METHODS: [SamplingMethod; 4] = [
+    SamplingMethod::Random,
+    SamplingMethod::Stratified,
+    SamplingMethod::Halton,
+    SamplingMethod::Sobol,
+];
+
+/// The first values of a sample, alternating between single values and pairs, far enough to
+/// reach past the table of Halton bases.
+fn sample_values(sampler: &mut Sampler, x: i32, y: i32, index: u32) -> Vec<f64> {
+    sampler.start_sample(x, y, index);
+    let mut values = Vec::new();
+    for _ in 0..30 {
+        values.push(sampler.get_1d());
+        let (a, b) = sampler.get_2d();
+        values.push(a);
+        values.push(b);
+    }
+    return values;
+}
+
+#[test]
+fn values_are_in_the_unit_interval() {
+    for method in METHODS.iter() {
+        for samples in [1, 7, 16, 100] {
+            let mut sampler = Sampler::new(*method, samples, 42);
+            for (x, y) in [(0, 0), (3, 9), (511, 280), (-1, 4)] {
+                for index in 0..samples {
+                    for value in sample_values(&mut sampler, x, y, index) {
+                        assert!((0.0..1.0).contains(&value), "{:?} gave {}", method, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stratified_samples_land_one_per_stratum() {
+    for samples in [1, 5, 16, 30, 64] {
+        let mut sampler = Sampler::new(SamplingMethod::Stratified, samples, 3);
+        let columns = (samples as f64).sqrt().ceil() as u32;
+        let rows = (samples + columns - 1) / columns;
+        for (x, y) in [(0, 0), (12, 7)] {
+            // Every dimension is stratified on its own.
+            for dimension in 0..6 {
+                let mut strata_1d = HashSet::new();
+                let mut cells_2d = HashSet::new();
+                for index in 0..samples {
+                    sampler.start_sample(x, y, index);
+                    for _ in 0..dimension {
+                        sampler.get_2d();
+                    }
+                    let value = sampler.get_1d();
+                    let (u, v) = sampler.get_2d();
+                    strata_1d.insert((value * samples as f64) as u32);
+                    cells_2d.insert(((u * columns as f64) as u32, (v * rows as f64) as u32));
+                }
+                assert_eq!(strata_1d.len(), samples as usize, "{} samples share 1D strata", samples);
+                assert_eq!(cells_2d.len(), samples as usize, "{} samples share 2D cells", samples);
+            }
+        }
+    }
+}
+
+#[test]
+fn sobol_samples_land_one_per_elementary_interval() {
+    // The scrambled Sobol points of a pixel keep the property of the sequence that every power of
+    // two of them puts one point into each cell of a grid with that many cells.
+    let samples = 256;
+    let mut sampler = Sampler::new(SamplingMethod::Sobol, samples, 11);
+    let points: Vec<(f64, f64)> = (0..samples)
+        .map(|index| {
+            sampler.start_sample(5, 8, index);
+            sampler.get_1d();
+            sampler.get_2d()
+        })
+        .collect();
+    for columns_log in 0..=8 {
+        let columns = 1u32 << columns_log;
+        let rows = samples / columns;
+        let cells: HashSet<(u32, u32)> = points
+            .iter()
+            .map(|(u, v)| ((u * columns as f64) as u32, (v * rows as f64) as u32))
+            .collect();
+        assert_eq!(cells.len(), samples as usize, "points share a {}x{} cell", columns, rows);
+    }
+}
+
+#[test]
+fn values_do_not_depend_on_the_thread_or_the_order() {
+    // The Owen scrambling of every pixel and dimension is derived from the seed alone, so samples
+    // taken in another order, by another sampler or on another thread are the same.
+    for method in METHODS.iter().copied() {
+        let pixels: Vec<(i32, i32)> = (0..8).flat_map(|y| (0..8).map(move |x| (x, y))).collect();
+        let samples = 16;
+        let mut sampler = Sampler::new(method, samples, 99);
+        let expected: Vec<Vec<f64>> = pixels
+            .iter()
+            .flat_map(|(x, y)| (0..samples).map(move |index| (*x, *y, index)))
+            .map(|(x, y, index)| sample_values(&mut sampler, x, y, index))
+            .collect();
+
+        let threads: Vec<_> = (0..4)
+            .map(|thread| {
+                let pixels = pixels.clone();
+                thread::spawn(move || {
+                    let mut sampler = Sampler::new(method, samples, 99);
+                    let mut values = Vec::new();
+                    for (p, (x, y)) in pixels.iter().enumerate().rev().filter(|(p, _)| p % 4 == thread) {
+                        for index in (0..samples).rev() {
+                            values.push((p * samples as usize + index as usize, sample_values(&mut sampler, *x, *y, index)));
+                        }
+                    }
+                    values
+                })
+            })
+            .collect();
+        for handle in threads {
+            for (i, values) in handle.join().unwrap() {
+                assert_eq!(values, expected[i], "{:?} gave other values on another thread", method);
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Mistakes in a scene file must be reported with the line they are on.
+
+use std::fs;
+use std::path::PathBuf;
+
+use raytracer::parsers::scene::load_scene;
+
+/// A path in the temporary directory that no other test run uses.
+fn temporary_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name));
+}
+
+/// A valid scene of 17 lines, with `camera` as the third line and `object` as the sixteenth.
+fn scene(camera: &str, object: &str) -> String {
+    return format!(
+        r#"[camera]
+look_from = [0, 1, 4]
+{}
+look_at = [0, 0, 0]
+vfov = 40
+[textures]
+grey = {{ solid = {{ color = [0.5, 0.5, 0.5] }} }}
+
+[materials]
+grey = {{ diffuse = {{ albedo = "grey" }} }}
+
+[[spheres]]
+position = [0, 0, 0]
+radius = 1
+material = "grey"
+{}
+"#,
+        camera, object
+    );
+}
+
+/// Loads the scene and checks that it fails on `line` with a message that contains `message`.
+fn assert_error(name: &str, source: &str, line: usize, message: &str) {
+    let path = temporary_path(name);
+    fs::write(&path, source).unwrap();
+    let result = load_scene(&path);
+    fs::remove_file(&path).unwrap();
+    let error = result.err().expect("a malformed scene was loaded").to_string();
+    let location = format!("{}:{}: ", path.display(), line);
+    assert!(error.starts_with(&location), "`{}` is not reported at line {}", error, line);
+    assert!(error.contains(message), "`{}` does not say `{}`", error, message);
+}
+
+#[test]
+fn valid_scene_loads() {
+    let path = temporary_path("valid.toml");
+    fs::write(&path, scene("", "")).unwrap();
+    let result = load_scene(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_ok(), "{}", result.err().unwrap());
+}
+
+#[test]
+fn syntax_errors_have_a_line() {
+    assert_error("syntax.toml", &scene("aperture = ", ""), 3, "invalid string");
+    assert_error("unknown-field.toml", &scene("", "colour = [1, 0, 0]"), 16, "unknown field `colour`");
+    assert_error("wrong-type.toml", &scene("aperture = \"wide\"", ""), 3, "expected f64");
+}
+
+#[test]
+fn render_settings_errors_have_a_line() {
+    let render = |setting: &str| format!("{}\n\n[render]\nwidth = 20\n{}", scene("", ""), setting);
+    // The scene has 17 lines, then an empty one, the table header and the width.
+    assert_error("samples.toml", &render("samples_per_pixel = 0"), 21, "samples per pixel must be positive");
+    assert_error("depth.toml", &render("depth = 0"), 21, "depth must be positive");
+    assert_error("radius.toml", &render("filter_radius = nan"), 21, "filter radius must be positive");
+    assert_error("threshold.toml", &render("noise_threshold = -0.5"), 21, "noise threshold must be positive");
+    assert_error("bounces.toml", &render("min_bounces = -1"), 21, "must not be negative");
+    assert_error("size.toml", &render("height = 0"), 21, "image size must be positive");
+}
+
+#[test]
+fn object_errors_have_a_line() {
+    assert_error("material.toml", &scene("", "").replace("material = \"grey\"", "material = \"gold\""), 15, "unknown material `gold`");
+    assert_error(
+        "motion.toml",
+        &scene("", "motion = [{ time = 0 },\n  { time = 1, scale = [1, 0, 1] }]"),
+        17,
+        "scale must be positive",
+    );
+}
+
+#[test]
+fn material_errors_have_a_line() {
+    let material = "[materials.shiny.cook_torrance]\ndiffuse = \"grey\"\nspecular = \"grey\"\nroughness = 0.3\nk_d = 1.5";
+    assert_error("k_d.toml", &scene("", material), 20, "k_d must be between 0 and 1");
+}
+
+#[test]
+fn light_errors_have_a_line() {
+    let rect = "[[lights]]\n[lights.rect]\ncorner = [0, 2, 0]\nedge_u = [1, 0, 0]\nedge_v = [2, 0, 0]\nemission = [1, 1, 1]";
+    assert_error("rect.toml", &scene("", rect), 19, "has no area");
+    let disc = "[[lights]]\n[lights.disc]\ncenter = [0, 2, 0]\nnormal = [0, -1, 0]\nradius = 0\nemission = [1, 1, 1]";
+    assert_error("disc.toml", &scene("", disc), 20, "radius must be positive");
+}
@@ -0,0 +1,170 @@
+//! White furnace tests: a surface that reflects all light must look exactly like a uniformly white
+//! environment, and one that absorbs some may only look darker, never brighter.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use raytracer::hittables::hittable::{HitRecord, Hittable};
+use raytracer::hittables::sphere::Sphere;
+use raytracer::lights::environment::Background;
+use raytracer::material::Material;
+use raytracer::microfacet::Ggx;
+use raytracer::samplers::sampler::{Sampler, SamplingMethod};
+use raytracer::samplers::warp::{sample_hemisphere, sample_sphere};
+use raytracer::vec3::{Color, Vec3};
+use raytracer::{create_camera, render, Scene, Texture};
+
+const WHITE: Color = Color { e: [1.0, 1.0, 1.0] };
+
+fn cook_torrance(k_d: f64, roughness: f64) -> Material {
+    return Material::CookTorrance {
+        diffuse: Texture::Solid { color: WHITE },
+        k_d,
+        specular: Texture::Solid { color: WHITE },
+        roughness,
+        emission: Vec3::new(),
+    };
+}
+
+fn hit_record(material: Material) -> HitRecord {
+    return HitRecord {
+        point: Vec3::new(),
+        normal: Vec3 { e: [0.0, 0.0, 1.0] },
+        material: Arc::new(material),
+        t: 1.0,
+        u: 0.0,
+        v: 0.0,
+        front_face: true,
+        object: 0,
+    };
+}
+
+/// The direction at `degrees` from the normal.
+fn direction(degrees: f64) -> Vec3 {
+    let theta = degrees.to_radians();
+    return Vec3 { e: [theta.sin(), 0.0, theta.cos()] };
+}
+
+/// The fraction of the light from a white environment that is reflected towards `w_o`, estimated
+/// with the importance sampling of the material.
+fn reflectance(rec: &HitRecord, w_o: &Vec3, samples: u32) -> f64 {
+    let mut sampler = Sampler::new(SamplingMethod::Sobol, samples, 0);
+    let mut sum = 0.0;
+    for index in 0..samples {
+        sampler.start_sample(0, 0, index);
+        if let Some(sample) = rec.material.sample(w_o, rec, &mut sampler) {
+            sum += sample.attenuation.x();
+        }
+    }
+    return sum / samples as f64;
+}
+
+/// Like `reflectance`, but sampling the hemisphere uniformly and evaluating the material.
+fn uniform_reflectance(rec: &HitRecord, w_o: &Vec3, samples: u32) -> f64 {
+    let mut sampler = Sampler::new(SamplingMethod::Sobol, samples, 0);
+    let mut sum = 0.0;
+    for index in 0..samples {
+        sampler.start_sample(0, 0, index);
+        let w_i = sample_hemisphere(sampler.get_2d());
+        sum += rec.material.eval(w_o, &w_i, rec).x() * 2.0 * PI;
+    }
+    return sum / samples as f64;
+}
+
+#[test]
+fn visible_normals_integrate_to_one() {
+    // The weak white furnace test: the projected area of the visible microfacets equals the
+    // projected area of the surface, so D G1 / (4 cos_o) integrates to one over all reflected
+    // directions, including those that end up below the surface.
+    for alpha in [0.2, 0.5, 1.0] {
+        let ggx = Ggx::new(alpha);
+        for degrees in [0.0, 45.0, 80.0] {
+            let w_o = direction(degrees);
+            let samples = 1 << 18;
+            let mut sampler = Sampler::new(SamplingMethod::Sobol, samples, 0);
+            let mut sum = 0.0;
+            for index in 0..samples {
+                sampler.start_sample(0, 0, index);
+                let w_i = sample_sphere(sampler.get_2d());
+                let h = (w_o + w_i).unit_vector();
+                sum += ggx.d(&h) * ggx.g1(&w_o) / (4.0 * w_o.z()) * 4.0 * PI;
+            }
+            let integral = sum / samples as f64;
+            assert!((integral - 1.0).abs() < 0.02, "alpha {} at {} degrees integrates to {}", alpha, degrees, integral);
+        }
+    }
+}
+
+#[test]
+fn materials_never_reflect_more_than_all_light() {
+    for (k_d, roughness) in [(0.0, 0.05), (0.0, 0.3), (0.0, 1.0), (0.5, 0.3), (1.0, 0.5)] {
+        let rec = hit_record(cook_torrance(k_d, roughness));
+        for degrees in [0.0, 30.0, 60.0, 85.0] {
+            let w_o = direction(degrees);
+            let sampled = reflectance(&rec, &w_o, 1 << 14);
+            assert!(sampled <= 1.005, "k_d {} roughness {} at {} degrees reflects {}", k_d, roughness, degrees, sampled);
+            // Microfacets lose the light they reflect into each other, the more the rougher they are
+            // and the flatter the view, but a diffuse surface keeps everything.
+            let least = if k_d == 1.0 {
+                1.0 - 1e-9
+            } else if roughness <= 0.05 && degrees <= 60.0 {
+                0.98
+            } else {
+                0.25
+            };
+            assert!(sampled > least, "k_d {} roughness {} at {} degrees reflects {}", k_d, roughness, degrees, sampled);
+        }
+    }
+}
+
+#[test]
+fn sampling_density_matches_the_samples() {
+    // If `pdf` did not describe the distribution of `sample`, importance sampling would converge
+    // to a different value than uniform sampling.
+    for (k_d, roughness) in [(0.0, 0.4), (0.0, 1.0), (0.5, 0.5)] {
+        let rec = hit_record(cook_torrance(k_d, roughness));
+        for degrees in [0.0, 50.0, 75.0] {
+            let w_o = direction(degrees);
+            let sampled = reflectance(&rec, &w_o, 1 << 14);
+            let uniform = uniform_reflectance(&rec, &w_o, 1 << 18);
+            assert!(
+                (sampled - uniform).abs() < 0.02,
+                "k_d {} roughness {} at {} degrees: {} sampled, {} uniform",
+                k_d, roughness, degrees, sampled, uniform
+            );
+        }
+    }
+}
+
+#[test]
+fn white_sphere_vanishes_in_white_furnace() {
+    // A white diffuse surface reflects all light, the microfacet one loses a little to masking.
+    let diffuse = Material::Diffuse { albedo: Texture::Solid { color: WHITE }, emission: Vec3::new() };
+    for (material, darkest) in [(diffuse, 1.0 - 1e-9), (cook_torrance(0.5, 0.2), 0.9)] {
+        let sphere = Sphere {
+            position: Vec3::new(),
+            radius: 1.0,
+            material: Arc::new(material),
+        };
+        let camera = create_camera(
+            Vec3 { e: [0.0, 0.0, 4.0] },
+            Vec3::new(),
+            Vec3 { e: [0.0, 1.0, 0.0] },
+            30.0,
+            1.0,
+            4.0,
+            0.0,
+        );
+        let mut scene = Scene::new(camera, vec![Arc::new(Hittable::Sphere { sphere })]);
+        scene.background = Background::Solid { color: WHITE };
+        scene.settings.width = 16;
+        scene.settings.height = 16;
+        scene.settings.samples_per_pixel = 256;
+
+        let image = render(&scene, &scene.settings);
+        for pixel in image.pixels.iter() {
+            assert!(pixel.x() < 1.01, "a pixel is brighter than the furnace: {}", pixel.x());
+            assert!(pixel.x() > darkest, "a pixel is darker than the furnace: {}", pixel.x());
+        }
+    }
+}